@@ -0,0 +1,67 @@
+//! Radix-2 fixed-point FFT over [`FFT_LEN`] points. Every stage halves its
+//! outputs, so the result is the DFT divided by `FFT_LEN` and i16-range input
+//! cannot overflow.
+
+use core::f32::consts::TAU;
+
+use libm::{cosf, sinf};
+
+use super::FFT_LEN;
+
+const STAGES: u32 = FFT_LEN.trailing_zeros();
+
+/// Twiddle factors (Q15) for one FFT size.
+pub struct Fft {
+    cos: [i16; FFT_LEN / 2],
+    sin: [i16; FFT_LEN / 2],
+}
+
+impl Fft {
+    pub fn new() -> Self {
+        let mut cos = [0; FFT_LEN / 2];
+        let mut sin = [0; FFT_LEN / 2];
+        for k in 0..FFT_LEN / 2 {
+            let a = -TAU * k as f32 / FFT_LEN as f32;
+            cos[k] = (cosf(a) * 32767.0) as i16;
+            sin[k] = (sinf(a) * 32767.0) as i16;
+        }
+        Self { cos, sin }
+    }
+
+    /// Transform `re` + j`im` in place (natural order in and out).
+    pub fn run(&self, re: &mut [i32; FFT_LEN], im: &mut [i32; FFT_LEN]) {
+        for i in 0..FFT_LEN {
+            let j = (i as u32).reverse_bits() >> (32 - STAGES);
+            let j = j as usize;
+            if j > i {
+                re.swap(i, j);
+                im.swap(i, j);
+            }
+        }
+        let mut len = 2;
+        while len <= FFT_LEN {
+            let half = len / 2;
+            let step = FFT_LEN / len;
+            for start in (0..FFT_LEN).step_by(len) {
+                for k in 0..half {
+                    let (wr, wi) = (self.cos[k * step] as i64, self.sin[k * step] as i64);
+                    let (a, b) = (start + k, start + k + half);
+                    let (br, bi) = (re[b] as i64, im[b] as i64);
+                    let tr = ((br * wr - bi * wi) >> 15) as i32;
+                    let ti = ((br * wi + bi * wr) >> 15) as i32;
+                    re[b] = (re[a] - tr) >> 1;
+                    im[b] = (im[a] - ti) >> 1;
+                    re[a] = (re[a] + tr) >> 1;
+                    im[a] = (im[a] + ti) >> 1;
+                }
+            }
+            len *= 2;
+        }
+    }
+}
+
+impl Default for Fft {
+    fn default() -> Self {
+        Self::new()
+    }
+}
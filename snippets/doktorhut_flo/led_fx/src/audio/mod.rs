@@ -0,0 +1,245 @@
+//! Music analysis for the audio-reactive effects, in fixed point: blocks of
+//! [`FFT_LEN`] samples at [`SAMPLE_RATE`] go through a windowed FFT into
+//! [`BANDS`] band levels, an overall level and a beat detector. Levels are
+//! auto-ranged (the loudest recent band reads full scale), so a quiet mic and
+//! a line-level tap look alike. Platform-independent, so it runs on recordings
+//! on the host (see [`wav`]).
+
+mod fft;
+pub mod wav;
+
+use core::f32::consts::TAU;
+
+use libm::cosf;
+
+pub use fft::Fft;
+
+/// Rate the analyser expects (use a [`Resampler`] for other material).
+pub const SAMPLE_RATE: u32 = 8000;
+/// Samples per analysed block: 32 ms, 31.25 Hz per FFT bin.
+pub const FFT_LEN: usize = 256;
+/// Number of frequency bands.
+pub const BANDS: usize = 8;
+/// First FFT bin of each band, and the end of the last one: roughly octaves
+/// from 31 Hz to 4 kHz, finer in the bass.
+pub const BAND_EDGES: [usize; BANDS + 1] = [1, 2, 3, 5, 9, 16, 32, 64, 128];
+
+// Levels are log2 energies in Q8. The displayed range spans this many (6 dB
+// each) below the auto-range reference...
+const RANGE_Q8: i32 = 8 * 256;
+// ...which falls this much per block after a loud passage (~3 dB/s)...
+const AGC_FALL_Q8: i32 = 4;
+// ...but never below this floor plus the range, so silence stays dark.
+const FLOOR_Q8: i32 = 12 * 256;
+// Bands summed for beat detection (up to ~156 Hz).
+const BASS_BANDS: usize = 3;
+// A beat is bass energy this far above its recent level (2x)...
+const BEAT_RISE_Q8: i32 = 256;
+// ...at least this many blocks (~260 ms) after the previous one.
+const BEAT_MIN_BLOCKS: u32 = 8;
+// The recent bass level follows rises at once and falls 1/16 of the way per
+// block (~0.5 s), so a steady bass is no beat but the next kick is.
+const BASS_FALL_SHIFT: u32 = 4;
+
+/// What the analyser made of one block.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Analysis {
+    /// Band levels, 0..=65535 (bass first).
+    pub bands: [u16; BANDS],
+    /// Overall level, 0..=65535.
+    pub level: u16,
+    /// A beat starts in this block.
+    pub beat: bool,
+}
+
+/// The music as effects see it (part of [`Ctx`](crate::effects::Ctx)).
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Audio {
+    pub bands: [u16; BANDS],
+    pub level: u16,
+    /// Time since the last beat (`u32::MAX`: none yet).
+    pub since_beat_ms: u32,
+}
+
+impl Audio {
+    pub const SILENT: Audio = Audio {
+        bands: [0; BANDS],
+        level: 0,
+        since_beat_ms: u32::MAX,
+    };
+}
+
+impl Default for Audio {
+    fn default() -> Self {
+        Self::SILENT
+    }
+}
+
+/// log2(x) in Q8 (0 for 0), linear between powers of two.
+fn log2_q8(x: u64) -> i32 {
+    if x == 0 {
+        return 0;
+    }
+    let n = 63 - x.leading_zeros();
+    let frac = if n >= 8 { x >> (n - 8) } else { x << (8 - n) } & 0xFF;
+    (n * 256 + frac as u32) as i32
+}
+
+/// Auto-ranging: follows the loudest input up at once, down slowly.
+struct Agc {
+    ref_q8: i32,
+}
+
+impl Agc {
+    fn update(&mut self, peak_q8: i32) {
+        self.ref_q8 = (self.ref_q8 - AGC_FALL_Q8)
+            .max(peak_q8)
+            .max(FLOOR_Q8 + RANGE_Q8);
+    }
+
+    /// `log_q8` on the 0..=65535 scale below the reference.
+    fn level(&self, log_q8: i32) -> u16 {
+        let above = (log_q8 - (self.ref_q8 - RANGE_Q8)).clamp(0, RANGE_Q8);
+        (above * 65535 / RANGE_Q8) as u16
+    }
+}
+
+/// Turns samples into [`Analysis`] results, one per [`FFT_LEN`] samples.
+pub struct Analyser {
+    fft: Fft,
+    window: [i16; FFT_LEN],
+    block: [i16; FFT_LEN],
+    filled: usize,
+    re: [i32; FFT_LEN],
+    im: [i32; FFT_LEN],
+    bands_agc: Agc,
+    level_agc: Agc,
+    bass_q8: i32,
+    since_beat: u32,
+}
+
+impl Analyser {
+    pub fn new() -> Self {
+        // Hann window, Q15.
+        let mut window = [0; FFT_LEN];
+        for (i, w) in window.iter_mut().enumerate() {
+            *w = ((1.0 - cosf(TAU * i as f32 / FFT_LEN as f32)) * 0.5 * 32767.0) as i16;
+        }
+        let agc = || Agc {
+            ref_q8: FLOOR_Q8 + RANGE_Q8,
+        };
+        Self {
+            fft: Fft::new(),
+            window,
+            block: [0; FFT_LEN],
+            filled: 0,
+            re: [0; FFT_LEN],
+            im: [0; FFT_LEN],
+            bands_agc: agc(),
+            level_agc: agc(),
+            bass_q8: 0,
+            since_beat: BEAT_MIN_BLOCKS,
+        }
+    }
+
+    /// Add one sample; returns the block's analysis once it is full.
+    pub fn push(&mut self, sample: i16) -> Option<Analysis> {
+        self.block[self.filled] = sample;
+        self.filled += 1;
+        if self.filled < FFT_LEN {
+            return None;
+        }
+        self.filled = 0;
+        let block = self.block;
+        Some(self.analyse(&block))
+    }
+
+    /// Analyse one block of samples (DC is removed first).
+    pub fn analyse(&mut self, block: &[i16; FFT_LEN]) -> Analysis {
+        let mean = block.iter().map(|&s| s as i32).sum::<i32>() / FFT_LEN as i32;
+        for ((re, &s), &w) in self.re.iter_mut().zip(block).zip(&self.window) {
+            *re = ((s as i32 - mean) * w as i32) >> 15;
+        }
+        self.im = [0; FFT_LEN];
+        self.fft.run(&mut self.re, &mut self.im);
+
+        let mut energy = [0u64; BANDS];
+        for (b, e) in energy.iter_mut().enumerate() {
+            for k in BAND_EDGES[b]..BAND_EDGES[b + 1] {
+                let (re, im) = (self.re[k] as i64, self.im[k] as i64);
+                *e += (re * re + im * im) as u64;
+            }
+        }
+        let logs = energy.map(log2_q8);
+        let total = log2_q8(energy.iter().sum());
+
+        self.bands_agc
+            .update(logs.iter().copied().max().unwrap_or(0));
+        self.level_agc.update(total);
+        let bands = logs.map(|l| self.bands_agc.level(l));
+        let level = self.level_agc.level(total);
+
+        // Beat: the bass jumps well above its recent level.
+        let bass = log2_q8(energy[..BASS_BANDS].iter().sum());
+        self.since_beat = self.since_beat.saturating_add(1);
+        let beat = bass > self.bass_q8 + BEAT_RISE_Q8
+            && bass > FLOOR_Q8
+            && self.since_beat >= BEAT_MIN_BLOCKS;
+        if beat {
+            self.since_beat = 0;
+        }
+        self.bass_q8 = if bass > self.bass_q8 {
+            bass
+        } else {
+            self.bass_q8 + ((bass - self.bass_q8) >> BASS_FALL_SHIFT)
+        };
+
+        Analysis { bands, level, beat }
+    }
+}
+
+impl Default for Analyser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Converts a recording's sample rate to [`SAMPLE_RATE`]: averages the input
+/// samples falling into each output sample when going down (a crude
+/// anti-alias filter), repeats them when going up.
+pub struct Resampler {
+    /// Input samples per output sample, Q16.
+    step_q16: u32,
+    pos_q16: u32,
+    acc: i32,
+    n: i32,
+    last: i16,
+}
+
+impl Resampler {
+    pub fn new(from_rate: u32) -> Self {
+        Self {
+            step_q16: (((from_rate as u64) << 16) / SAMPLE_RATE as u64).max(1) as u32,
+            pos_q16: 0,
+            acc: 0,
+            n: 0,
+            last: 0,
+        }
+    }
+
+    /// Feed one input sample; `out` gets every output sample it completes.
+    pub fn push(&mut self, sample: i16, mut out: impl FnMut(i16)) {
+        self.acc += sample as i32;
+        self.n += 1;
+        self.pos_q16 += 1 << 16;
+        while self.pos_q16 >= self.step_q16 {
+            if self.n > 0 {
+                self.last = (self.acc / self.n) as i16;
+                self.acc = 0;
+                self.n = 0;
+            }
+            out(self.last);
+            self.pos_q16 -= self.step_q16;
+        }
+    }
+}
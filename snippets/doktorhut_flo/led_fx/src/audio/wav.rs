@@ -0,0 +1,84 @@
+//! Minimal RIFF/WAVE reader: 16-bit PCM at any rate and channel count, mixed
+//! down to mono. Enough to feed recordings to the analyser on the host.
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum WavError {
+    /// Not a RIFF/WAVE file, or no `fmt `/`data` chunk.
+    NotWav,
+    /// Compressed, or not 16 bits per sample.
+    Unsupported,
+}
+
+/// A parsed WAV file, borrowing its sample data.
+pub struct Wav<'a> {
+    pub sample_rate: u32,
+    pub channels: u16,
+    data: &'a [u8],
+}
+
+fn u16_at(b: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(b.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_at(b: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(b.get(at..at + 4)?.try_into().ok()?))
+}
+
+impl<'a> Wav<'a> {
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, WavError> {
+        if bytes.get(0..4) != Some(b"RIFF") || bytes.get(8..12) != Some(b"WAVE") {
+            return Err(WavError::NotWav);
+        }
+        let mut format = None;
+        let mut data = None;
+        let mut at = 12;
+        while let (Some(id), Some(size)) = (bytes.get(at..at + 4), u32_at(bytes, at + 4)) {
+            let body = at + 8;
+            // A truncated last chunk keeps what is there.
+            let end = (body + size as usize).min(bytes.len());
+            match id {
+                b"fmt " => format = Some(&bytes[body..end]),
+                b"data" => data = Some(&bytes[body..end]),
+                _ => {}
+            }
+            at = body + size as usize + (size as usize & 1); // chunks are word aligned
+        }
+        let (Some(fmt), Some(data)) = (format, data) else {
+            return Err(WavError::NotWav);
+        };
+        let tag = u16_at(fmt, 0).ok_or(WavError::NotWav)?;
+        let channels = u16_at(fmt, 2).ok_or(WavError::NotWav)?;
+        let sample_rate = u32_at(fmt, 4).ok_or(WavError::NotWav)?;
+        let bits = u16_at(fmt, 14).ok_or(WavError::NotWav)?;
+        // 1 = PCM, 0xFFFE = extensible (PCM for our purposes at 16 bits).
+        if !matches!(tag, 1 | 0xFFFE) || bits != 16 || channels == 0 {
+            return Err(WavError::Unsupported);
+        }
+        Ok(Self {
+            sample_rate,
+            channels,
+            data,
+        })
+    }
+
+    /// Number of (mono) samples.
+    pub fn len(&self) -> usize {
+        self.data.len() / (2 * self.channels as usize)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The samples, channels averaged.
+    pub fn samples(&self) -> impl Iterator<Item = i16> + 'a {
+        let channels = self.channels as usize;
+        self.data.chunks_exact(2 * channels).map(move |frame| {
+            let sum: i32 = frame
+                .chunks_exact(2)
+                .map(|s| i16::from_le_bytes([s[0], s[1]]) as i32)
+                .sum();
+            (sum / channels as i32) as i16
+        })
+    }
+}
@@ -0,0 +1,112 @@
+//! 16-bit colour pipeline. Effects render 16-bit values where blends and fades
+//! are plain multiplies; the brightness curve and gamma are applied once, per
+//! channel, when the driver encodes a frame for the wire.
+
+use crate::Rgb;
+
+/// Expand an 8-bit colour to the 16-bit framebuffer range (255 -> 65535).
+pub const fn rgb(r: u8, g: u8, b: u8) -> Rgb {
+    [r as u16 * 257, g as u16 * 257, b as u16 * 257]
+}
+
+/// Scale every channel of `c` by `q16` (0..=65535 = 0..1).
+pub fn scale(c: Rgb, q16: u32) -> Rgb {
+    c.map(|v| (v as u32 * q16 / 65535) as u16)
+}
+
+/// Output scale (Q16 of full duty) for brightness levels 1..=8, evenly spaced
+/// in perceived lightness (CIE L* = 12.5 * level) rather than halving per step.
+const BRIGHTNESS_CURVE: [u16; 8] = [972, 2894, 6429, 12071, 20310, 31639, 46550, 65535];
+
+/// Q16 output scale for a `control` brightness level (1..=8).
+pub fn brightness_q16(level: u32) -> u32 {
+    BRIGHTNESS_CURVE[(level.clamp(1, 8) - 1) as usize] as u32
+}
+
+// Gamma 2.5, sampled at 257 points (index = value >> 8, the last entry closes
+// the top segment) and linearly interpolated in between.
+#[rustfmt::skip]
+static GAMMA_LUT: [u16; 257] = [
+    0, 0, 0, 1, 2, 3, 6, 8,
+    11, 15, 20, 25, 31, 38, 46, 54,
+    64, 74, 86, 98, 112, 126, 142, 159,
+    176, 195, 215, 237, 259, 283, 308, 334,
+    362, 391, 421, 453, 486, 520, 556, 594,
+    632, 673, 714, 758, 803, 849, 897, 946,
+    998, 1050, 1105, 1161, 1219, 1278, 1339, 1402,
+    1467, 1533, 1601, 1671, 1743, 1816, 1892, 1969,
+    2048, 2129, 2212, 2296, 2383, 2472, 2562, 2655,
+    2749, 2846, 2944, 3045, 3147, 3252, 3358, 3467,
+    3578, 3691, 3805, 3923, 4042, 4163, 4287, 4412,
+    4540, 4670, 4803, 4937, 5074, 5213, 5354, 5498,
+    5644, 5792, 5942, 6095, 6250, 6407, 6567, 6729,
+    6894, 7061, 7230, 7402, 7576, 7752, 7931, 8113,
+    8297, 8483, 8672, 8864, 9058, 9254, 9453, 9655,
+    9859, 10066, 10275, 10487, 10701, 10918, 11138, 11360,
+    11585, 11813, 12043, 12276, 12511, 12750, 12991, 13235,
+    13481, 13730, 13982, 14237, 14494, 14754, 15017, 15283,
+    15552, 15823, 16097, 16374, 16654, 16937, 17223, 17511,
+    17803, 18097, 18394, 18694, 18997, 19303, 19612, 19924,
+    20238, 20556, 20877, 21200, 21527, 21857, 22189, 22525,
+    22864, 23205, 23550, 23898, 24249, 24603, 24960, 25320,
+    25684, 26050, 26419, 26792, 27168, 27547, 27929, 28314,
+    28702, 29094, 29489, 29887, 30288, 30692, 31100, 31511,
+    31925, 32342, 32763, 33186, 33613, 34044, 34478, 34915,
+    35355, 35798, 36245, 36696, 37149, 37606, 38066, 38530,
+    38997, 39467, 39941, 40418, 40899, 41383, 41870, 42361,
+    42856, 43353, 43855, 44359, 44867, 45379, 45894, 46413,
+    46935, 47460, 47989, 48522, 49058, 49598, 50141, 50688,
+    51238, 51792, 52350, 52911, 53475, 54044, 54615, 55191,
+    55770, 56353, 56939, 57529, 58123, 58720, 59321, 59926,
+    60534, 61147, 61762, 62382, 63005, 63632, 64263, 64897,
+    65535,
+];
+
+/// Framebuffer value -> LED duty, both 16-bit.
+pub fn gamma(v: u16) -> u16 {
+    let i = (v >> 8) as usize;
+    let frac = (v & 0xFF) as u32;
+    let (a, b) = (GAMMA_LUT[i] as u32, GAMMA_LUT[i + 1] as u32);
+    (a + (b - a) * frac / 256) as u16
+}
+
+/// 16-bit LED duty of a framebuffer value: gamma, then brightness.
+pub fn duty(v: u16, bright_q16: u32) -> u32 {
+    gamma(v) as u32 * bright_q16 / 65535
+}
+
+/// One channel as it goes on the wire: [`duty`] rounded to 8 bits.
+pub fn encode_channel(v: u16, bright_q16: u32) -> u8 {
+    ((duty(v, bright_q16) + 128) / 257).min(255) as u8
+}
+
+/// Like [`encode_channel`], but carries the rounding remainder (in 1/256ths of
+/// an output step) from frame to frame in `err`. A value between two 8-bit
+/// steps then alternates between them and averages out right over time,
+/// instead of snapping to one (usually 0 at low brightness).
+pub fn encode_channel_dithered(v: u16, bright_q16: u32, err: &mut u8) -> u8 {
+    let acc = duty(v, bright_q16) * 256 / 257 + *err as u32; // Q8.8 of the 0..=255 output
+    let out = (acc >> 8).min(255);
+    *err = (acc - out * 256).min(255) as u8;
+    out as u8
+}
+
+/// 8-bit HSV -> RGB (hue 0..255).
+pub const fn hsv_to_rgb(h: u8, s: u8, v: u8) -> (u8, u8, u8) {
+    if s == 0 {
+        return (v, v, v);
+    }
+    let region = (h / 43) as u32;
+    let rem = (h % 43) as u32 * 6; // 0..252
+    let p = (v as u32 * (255 - s as u32) / 255) as u8;
+    let q = (v as u32 * (255 - s as u32 * rem / 255) / 255) as u8;
+    let t = (v as u32 * (255 - s as u32 * (255 - rem) / 255) / 255) as u8;
+    match region {
+        0 => (v, t, p),
+        1 => (q, v, p),
+        2 => (p, v, t),
+        3 => (p, q, v),
+        4 => (t, p, v),
+        _ => (v, p, q),
+    }
+}
@@ -0,0 +1,154 @@
+//! Layered compositor: a strip's frame is a stack of effects, bottom first,
+//! each rendered on its own and blended onto the layers below with a blend
+//! mode and an opacity (so e.g. a fade is just a layer's opacity).
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use crate::effects::{Compose, Ctx, Effect};
+use crate::{Framebuffer, Rgb, MAX_LEDS};
+
+// The layer scratch buffer lives in static memory, not the embassy task
+// arena. `run` renders the strips one after another and needs only one.
+static mut SCRATCH: [Rgb; MAX_LEDS] = [[0; 3]; MAX_LEDS];
+static SCRATCH_TAKEN: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Blend {
+    /// Source over destination.
+    Replace,
+    /// Sum, clamped.
+    Add,
+    /// Product (darkens; a grey layer is a dimmer).
+    Multiply,
+    /// Inverse product of the inverses (lightens without clipping).
+    Screen,
+    /// Per-channel maximum.
+    Max,
+}
+
+/// Which pixels a layer covers; `opacity_q16` applies within them.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Mask {
+    All,
+    /// From the data-in end up to this fraction (Q16) of the strip.
+    Head(u32),
+    /// From this fraction (Q16) of the strip to the far end.
+    Tail(u32),
+}
+
+impl Mask {
+    fn covers(self, i: usize, len: usize) -> bool {
+        let head = |front_q16: u32| ((i as u64) << 16) < front_q16 as u64 * len as u64;
+        match self {
+            Mask::All => true,
+            Mask::Head(front_q16) => head(front_q16),
+            Mask::Tail(front_q16) => !head(front_q16),
+        }
+    }
+}
+
+pub struct Layer<'a> {
+    pub effect: &'a mut dyn Effect,
+    pub blend: Blend,
+    /// 0..=65535 (transparent .. opaque).
+    pub opacity_q16: u32,
+    pub mask: Mask,
+}
+
+impl<'a> Layer<'a> {
+    /// Fully opaque `Replace` layer.
+    pub fn new(effect: &'a mut dyn Effect) -> Self {
+        Self {
+            effect,
+            blend: Blend::Replace,
+            opacity_q16: 65535,
+            mask: Mask::All,
+        }
+    }
+
+    pub fn with_blend(mut self, blend: Blend) -> Self {
+        self.blend = blend;
+        self
+    }
+
+    pub fn with_opacity_q16(mut self, opacity_q16: u32) -> Self {
+        self.opacity_q16 = opacity_q16.min(65535);
+        self
+    }
+
+    pub fn with_mask(mut self, mask: Mask) -> Self {
+        self.mask = mask;
+        self
+    }
+}
+
+/// Blend one channel of `src` onto `dst`.
+fn blend_channel(dst: u16, src: u16, blend: Blend) -> u16 {
+    let (d, s) = (dst as u32, src as u32);
+    let v = match blend {
+        Blend::Replace => s,
+        Blend::Add => (d + s).min(65535),
+        Blend::Multiply => d * s / 65535,
+        Blend::Screen => 65535 - (65535 - d) * (65535 - s) / 65535,
+        Blend::Max => d.max(s),
+    };
+    v as u16
+}
+
+/// `src` blended onto `dst`, then mixed with `dst` by `opacity_q16`. The mix
+/// is done in i64: a full 16-bit step times a full opacity overflows i32.
+pub fn blend_px(dst: Rgb, src: Rgb, blend: Blend, opacity_q16: u32) -> Rgb {
+    let mut out = dst;
+    for ((o, &d), &s) in out.iter_mut().zip(dst.iter()).zip(src.iter()) {
+        let b = blend_channel(d, s, blend) as i64;
+        *o = (d as i64 + (b - d as i64) * opacity_q16 as i64 / 65535) as u16;
+    }
+    out
+}
+
+pub struct Compositor<'a> {
+    scratch: &'a mut [Rgb; MAX_LEDS],
+}
+
+impl Compositor<'static> {
+    /// The compositor on the static scratch buffer (shared by the strips it
+    /// renders in turn). There is only one: `None` once it has been taken.
+    pub fn new() -> Option<Self> {
+        if SCRATCH_TAKEN.swap(true, Ordering::Relaxed) {
+            return None;
+        }
+        let slot = &raw mut SCRATCH;
+        // SAFETY: the buffer is handed out once (SCRATCH_TAKEN), so this &mut
+        // is unique.
+        let scratch = unsafe { &mut *slot };
+        Some(Self { scratch })
+    }
+}
+
+impl<'a> Compositor<'a> {
+    /// A compositor rendering its layers in `scratch`.
+    pub fn with_scratch(scratch: &'a mut [Rgb; MAX_LEDS]) -> Self {
+        Self { scratch }
+    }
+
+    /// Tick `layer`'s effect and blend it onto `fb` (the layers below it; clear
+    /// `fb` before the bottom one). Pixels outside the layer's mask are left
+    /// alone, as are those a `Compose::Overlay` effect leaves black.
+    pub fn draw(&mut self, layer: Layer, ctx: &Ctx, fb: &mut Framebuffer) {
+        layer.effect.tick(ctx);
+        if layer.opacity_q16 == 0 {
+            return;
+        }
+        let scratch = &mut self.scratch[..fb.len()];
+        scratch.fill([0, 0, 0]);
+        layer.effect.render(scratch);
+        let overlay = layer.effect.compose() == Compose::Overlay;
+        let len = fb.len();
+        for (i, (d, &s)) in fb.iter_mut().zip(scratch.iter()).enumerate() {
+            if (overlay && s == [0, 0, 0]) || !layer.mask.covers(i, len) {
+                continue;
+            }
+            *d = blend_px(*d, s, layer.blend, layer.opacity_q16);
+        }
+    }
+}
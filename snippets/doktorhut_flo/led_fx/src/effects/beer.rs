@@ -0,0 +1,153 @@
+//! The "BEER" shot: a byte, or a whole packet of them, fired once down the wire
+//! in a distinct color, overlaid on whatever else is on the strip. Travels
+//! slowly, then reports `is_finished()` once its last byte has left the strip.
+//!
+//! Laid out as a UART sends it: the first byte leads, each byte LSB first
+//! (so its MSB trails), with one idle cell between bytes.
+
+use super::motion::{draw_cell, Trail};
+use super::{Compose, Ctx, Effect, Param};
+use crate::color::rgb;
+use crate::{Framebuffer, Rgb};
+
+const BITS: i32 = 8;
+const Q8: i32 = 256;
+/// Longest packet a shot carries; longer ones are cut.
+pub const MAX_BYTES: usize = 16;
+// Idle cells between two bytes.
+const BYTE_GAP: i32 = 1;
+
+// Distinct from the cyan/violet stream.
+const BEER_ONE: Rgb = rgb(255, 80, 0); // orange
+const BEER_ZERO: Rgb = rgb(40, 12, 0);
+
+pub struct BeerByte {
+    bytes: [u8; MAX_BYTES],
+    len: usize,
+    bit_width: i32,
+    // Tail (last byte's MSB) position.
+    pos_q8: i32,
+    done: bool,
+    trail: Option<Trail>,
+}
+
+impl BeerByte {
+    pub fn new(byte: u8) -> Self {
+        let mut bytes = [0; MAX_BYTES];
+        bytes[0] = byte;
+        Self {
+            bytes,
+            len: 1,
+            bit_width: 1,
+            pos_q8: -BITS * Q8,
+            done: true,
+            trail: None,
+        }
+    }
+
+    /// Tunable as `bit_width`.
+    pub const PARAMS: &'static [Param] = &[Param::new("Bit width", 1, 8, 1)];
+
+    /// Draw each bit `width` LEDs wide.
+    pub fn with_bit_width(mut self, width: u32) -> Self {
+        self.bit_width = width.max(1) as i32;
+        self
+    }
+
+    /// Leave a trail fading with time constant `tau_ms`.
+    pub fn with_trail(mut self, tau_ms: u32) -> Self {
+        self.trail = Some(Trail::new(tau_ms));
+        self
+    }
+
+    /// Carry `bytes` (first sent first, at most [`MAX_BYTES`]) from the next
+    /// shot on.
+    pub fn set_bytes(&mut self, bytes: &[u8]) {
+        let n = bytes.len().min(MAX_BYTES);
+        self.bytes = [0; MAX_BYTES];
+        self.bytes[..n].copy_from_slice(&bytes[..n]);
+        self.len = n.max(1);
+    }
+
+    /// Length of the shot in cells.
+    fn width(&self) -> i32 {
+        self.len as i32 * (BITS * self.bit_width + BYTE_GAP) - BYTE_GAP
+    }
+
+    /// (Re)start the shot from off-screen left.
+    pub fn reset(&mut self) {
+        self.pos_q8 = -self.width() * Q8;
+        self.done = false;
+    }
+}
+
+impl Effect for BeerByte {
+    /// Entering fires a new shot.
+    fn on_enter(&mut self, _ctx: &Ctx) {
+        self.reset();
+    }
+
+    /// Advances by `ctx.velocity_q8` so it scales with the configured LED speed.
+    /// Finishes when its tail runs off the end of the actual strip (`ctx.len`).
+    fn tick(&mut self, ctx: &Ctx) {
+        if self.done {
+            return;
+        }
+        self.pos_q8 += ctx.advance_q8(ctx.velocity_q8).max(1);
+        if self.pos_q8 >> 8 >= ctx.len as i32 {
+            self.done = true;
+        }
+        if let Some(trail) = &mut self.trail {
+            trail.update(ctx.velocity_q8);
+        }
+    }
+
+    /// Draw the travelling byte (and its trail) onto a cleared `fb`; the
+    /// rest stays black for the compositor to overlay.
+    fn render(&self, fb: &mut Framebuffer) {
+        if self.done {
+            return;
+        }
+        for (j, &byte) in self.bytes[..self.len].iter().enumerate() {
+            let byte_cells = BITS * self.bit_width + BYTE_GAP;
+            let start_q8 = self.pos_q8 + (self.len - 1 - j) as i32 * byte_cells * Q8;
+            for k in 0..BITS {
+                let bit = (byte >> (7 - k)) & 1;
+                let color = if bit == 1 { BEER_ONE } else { BEER_ZERO };
+                for w in 0..self.bit_width {
+                    draw_cell(fb, start_q8 + (k * self.bit_width + w) * Q8, color);
+                }
+            }
+        }
+        // Behind the tail only: the idle cells between bytes stay dark.
+        if let Some(trail) = &self.trail {
+            let behind = ((self.pos_q8 >> 8) + 1).clamp(0, fb.len() as i32);
+            trail.apply(&mut fb[..behind as usize]);
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.done
+    }
+
+    fn compose(&self) -> Compose {
+        Compose::Overlay
+    }
+
+    fn params(&self) -> &'static [Param] {
+        Self::PARAMS
+    }
+
+    fn param(&self, index: usize) -> i32 {
+        match index {
+            0 => self.bit_width,
+            _ => 0,
+        }
+    }
+
+    fn set_param(&mut self, index: usize, value: i32) {
+        if index == 0 {
+            self.bit_width = Self::PARAMS[0].clamp(value);
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! A gentle breathing pulse of one colour (the MUSIC strip while music plays).
+//! Phase advances with tick time, not a frame counter, so it stays smooth even
+//! if the frame cadence stutters.
+
+use super::{Ctx, Effect, Param};
+use crate::color::scale;
+use crate::{Framebuffer, Rgb};
+
+pub struct Breathe {
+    color: Rgb,
+    period_ms: u32,
+    floor_q16: u32,
+    phase_ms: u32,
+}
+
+impl Breathe {
+    /// Tunable as `period_ms`.
+    pub const PARAMS: &'static [Param] = &[Param::new("Period", 500, 8000, 250)];
+
+    /// Triangle between `floor_q16` and full `color` over `period_ms`.
+    pub fn new(color: Rgb, period_ms: u32, floor_q16: u32) -> Self {
+        Self {
+            color,
+            period_ms: period_ms.max(1),
+            floor_q16,
+            phase_ms: 0,
+        }
+    }
+}
+
+impl Effect for Breathe {
+    fn on_enter(&mut self, _ctx: &Ctx) {
+        self.phase_ms = 0; // start each pulse from the dim floor
+    }
+
+    fn tick(&mut self, ctx: &Ctx) {
+        self.phase_ms = (self.phase_ms + ctx.dt_ms) % self.period_ms;
+    }
+
+    fn render(&self, fb: &mut Framebuffer) {
+        // Triangle in full 16-bit steps (an 8-bit level visibly staircases once
+        // gamma stretches the dark end).
+        let phase = (self.phase_ms as u64 * 131072 / self.period_ms as u64) as u32; // 0..131071
+        let tri = if phase < 65536 { phase } else { 131071 - phase };
+        let level = self.floor_q16 + tri * (65535 - self.floor_q16) / 65535;
+        fb.fill(scale(self.color, level));
+    }
+
+    fn params(&self) -> &'static [Param] {
+        Self::PARAMS
+    }
+
+    fn param(&self, index: usize) -> i32 {
+        match index {
+            0 => self.period_ms as i32,
+            _ => 0,
+        }
+    }
+
+    fn set_param(&mut self, index: usize, value: i32) {
+        if index == 0 {
+            self.period_ms = Self::PARAMS[0].clamp(value) as u32;
+            self.phase_ms %= self.period_ms;
+        }
+    }
+}
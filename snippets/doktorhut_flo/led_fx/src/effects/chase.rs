@@ -0,0 +1,61 @@
+//! Theatre chase: every `spacing`th LED lit, the pattern marching down the
+//! strip at the LED speed over a palette gradient.
+
+use super::{Ctx, Effect};
+use crate::palette::{Palette, Themed};
+use crate::Framebuffer;
+
+pub struct Chase {
+    palette: Themed,
+    spacing: u32,
+    offset_q8: u32,
+}
+
+impl Chase {
+    pub fn new() -> Self {
+        Self {
+            palette: Themed::new(),
+            spacing: 3,
+            offset_q8: 0,
+        }
+    }
+
+    /// Always use `palette` instead of the theme.
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette.pin(palette);
+        self
+    }
+
+    /// Light one LED in `spacing` (default 3).
+    pub fn with_spacing(mut self, spacing: u32) -> Self {
+        self.spacing = spacing.max(1);
+        self
+    }
+}
+
+impl Default for Chase {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Effect for Chase {
+    fn tick(&mut self, ctx: &Ctx) {
+        self.palette.resolve(ctx);
+        let step = ctx.advance_q8(ctx.velocity_q8).max(1) as u32;
+        self.offset_q8 = (self.offset_q8 + step) % (self.spacing * 256);
+    }
+
+    fn render(&self, fb: &mut Framebuffer) {
+        let len = fb.len().max(1) as u32;
+        let offset = self.offset_q8 >> 8;
+        for (i, px) in fb.iter_mut().enumerate() {
+            let i = i as u32;
+            *px = if i % self.spacing == offset {
+                self.palette.at((i * 255 / len) as u8)
+            } else {
+                [0; 3]
+            };
+        }
+    }
+}
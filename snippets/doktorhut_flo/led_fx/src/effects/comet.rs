@@ -0,0 +1,81 @@
+//! A comet: a bright head running down the strip at the LED speed and round
+//! again, its tail fading behind it. The head takes the palette colour of where
+//! it is along the strip.
+
+use super::motion::draw_cell;
+use super::{Ctx, Effect};
+use crate::color::scale;
+use crate::palette::{Palette, Themed};
+use crate::Framebuffer;
+
+// Tail cells dimmer than this (Q16) are not drawn.
+const TAIL_MIN_Q16: u32 = 256;
+
+pub struct Comet {
+    palette: Themed,
+    decay_q16: u32,
+    pos_q8: i32,
+    len: usize,
+}
+
+impl Comet {
+    pub fn new() -> Self {
+        Self {
+            palette: Themed::new(),
+            decay_q16: 49152,
+            pos_q8: 0,
+            len: 0,
+        }
+    }
+
+    /// Always use `palette` instead of the theme.
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette.pin(palette);
+        self
+    }
+
+    /// Share (Q16) of its brightness each tail cell keeps from the one in
+    /// front of it; higher = longer tail (default 0.75).
+    pub fn with_decay(mut self, decay_q16: u32) -> Self {
+        self.decay_q16 = decay_q16.min(65280);
+        self
+    }
+}
+
+impl Default for Comet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Effect for Comet {
+    fn tick(&mut self, ctx: &Ctx) {
+        self.palette.resolve(ctx);
+        self.len = ctx.len;
+        let span = ctx.len as i32 * 256;
+        if span > 0 {
+            self.pos_q8 = (self.pos_q8 + ctx.advance_q8(ctx.velocity_q8).max(1)) % span;
+        }
+    }
+
+    fn render(&self, fb: &mut Framebuffer) {
+        fb.fill([0; 3]);
+        let len = fb.len().min(self.len);
+        if len == 0 {
+            return;
+        }
+        let span = len as i32 * 256;
+        let head = self
+            .palette
+            .at((self.pos_q8 as u32 * 255 / span as u32) as u8);
+        let mut level = 65535;
+        for d in 0..len as i32 {
+            if level < TAIL_MIN_Q16 {
+                break;
+            }
+            let pos = (self.pos_q8 - d * 256).rem_euclid(span);
+            draw_cell(&mut fb[..len], pos, scale(head, level));
+            level = (level * self.decay_q16) >> 16;
+        }
+    }
+}
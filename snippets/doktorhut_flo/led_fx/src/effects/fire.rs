@@ -0,0 +1,101 @@
+//! 1D fire (the classic "Fire2012"): a heat value per LED cools at random,
+//! drifts up the strip from the data-in end and diffuses, and random sparks
+//! ignite near the base. Heat picks the colour from the palette (0 = coldest).
+
+use super::source::Random;
+use super::{Ctx, Effect};
+use crate::palette::{Palette, Themed};
+use crate::{Framebuffer, MAX_LEDS};
+
+// The simulation runs at a fixed rate, whatever the frame rate.
+const STEP_MS: u32 = 16;
+// Sparks start within this many LEDs of the base.
+const SPARK_ZONE: u32 = 6;
+
+pub struct Fire {
+    palette: Themed,
+    rng: Random,
+    cooling: u32,
+    sparking: u32,
+    heat: [u8; MAX_LEDS],
+    len: usize,
+    acc_ms: u32,
+}
+
+impl Fire {
+    pub fn new() -> Self {
+        Self {
+            palette: Themed::new(),
+            rng: Random::new(0xF12E_2012),
+            cooling: 55,
+            sparking: 120,
+            heat: [0; MAX_LEDS],
+            len: 0,
+            acc_ms: 0,
+        }
+    }
+
+    /// Always use `palette` instead of the theme.
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette.pin(palette);
+        self
+    }
+
+    /// `cooling` (0..=255): how fast the flames die down, so how short they
+    /// are; `sparking` (0..=255): chance of a new spark each step.
+    pub fn with_flames(mut self, cooling: u8, sparking: u8) -> Self {
+        self.cooling = cooling as u32;
+        self.sparking = sparking as u32;
+        self
+    }
+
+    fn step(&mut self) {
+        let len = self.len;
+        let heat = &mut self.heat[..len];
+        // Cool every cell a little; longer strips cool less per LED.
+        let max_cool = self.cooling * 10 / len as u32 + 2;
+        for h in heat.iter_mut() {
+            *h = h.saturating_sub(self.rng.range(0, max_cool).min(255) as u8);
+        }
+        // Heat drifts up and diffuses.
+        for k in (2..len).rev() {
+            heat[k] = ((heat[k - 1] as u32 + 2 * heat[k - 2] as u32) / 3) as u8;
+        }
+        // Now and then a spark near the base.
+        if self.rng.range(0, 255) < self.sparking {
+            let y = self.rng.range(0, SPARK_ZONE.min(len as u32) - 1) as usize;
+            heat[y] = heat[y].saturating_add(self.rng.range(160, 255) as u8);
+        }
+    }
+}
+
+impl Default for Fire {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Effect for Fire {
+    fn on_enter(&mut self, _ctx: &Ctx) {
+        self.heat = [0; MAX_LEDS];
+    }
+
+    fn tick(&mut self, ctx: &Ctx) {
+        self.palette.resolve(ctx);
+        self.len = ctx.len.min(MAX_LEDS);
+        if self.len == 0 {
+            return;
+        }
+        self.acc_ms += ctx.dt_ms;
+        while self.acc_ms >= STEP_MS {
+            self.acc_ms -= STEP_MS;
+            self.step();
+        }
+    }
+
+    fn render(&self, fb: &mut Framebuffer) {
+        for (i, px) in fb.iter_mut().enumerate() {
+            *px = self.palette.at(self.heat.get(i).copied().unwrap_or(0));
+        }
+    }
+}
@@ -0,0 +1,288 @@
+//! The byte stream as it goes over a real bus, for teaching: each byte wrapped
+//! in its protocol's framing, every framing element in its own colour. Bits
+//! are laid out in transmission order (the first one sent leads), data bits
+//! in the stream's cyan/violet.
+//!
+//! - [`Uart`] 8N1: start bit, 8 data bits LSB first, stop bit.
+//! - [`I2c`]: START, 7-bit address, R/W, ACK, data bytes each ACKed (the
+//!   last one read NACKed), STOP; repeated STARTs where the direction changes.
+//! - [`Spi`]: chip select around the bytes, each bit behind a clock cell.
+
+use super::source::{ByteSource, Gaps, Random};
+use super::stream::{Lane, BIT_ONE, BIT_ZERO};
+use super::{Ctx, Effect, Param};
+use crate::color::rgb;
+use crate::{Framebuffer, Rgb};
+
+const OFF: Rgb = rgb(0, 0, 0);
+
+const UART_START: Rgb = rgb(0, 255, 0); // green
+const UART_STOP: Rgb = rgb(255, 0, 0); // red
+
+const I2C_START: Rgb = rgb(255, 255, 255); // white
+const I2C_STOP: Rgb = rgb(255, 0, 0); // red
+const I2C_ADDR_ONE: Rgb = rgb(255, 150, 0); // amber
+const I2C_ADDR_ZERO: Rgb = rgb(40, 20, 0);
+const I2C_READ: Rgb = rgb(255, 0, 160); // magenta
+const I2C_WRITE: Rgb = rgb(40, 0, 24);
+const I2C_ACK: Rgb = rgb(0, 255, 0); // green
+const I2C_NACK: Rgb = rgb(0, 40, 0);
+
+const SPI_CS: Rgb = rgb(255, 200, 0); // yellow
+const SPI_CLK: Rgb = rgb(24, 24, 24); // dim white clock lane
+
+// Data bytes per I2C transaction / SPI chip-select frame.
+const BYTES_PER_FRAME: usize = 2;
+
+/// Upcoming cells of the frame being sent.
+pub struct Frame {
+    cells: [Rgb; Frame::CAP],
+    len: usize,
+    next: usize,
+}
+
+impl Frame {
+    // The longest frame (SPI: CS, 2 x 8 x 2 bit cells, CS) plus the longest
+    // gap (see `Gaps::PARAMS`).
+    const CAP: usize = 34 + 32;
+
+    const fn new() -> Self {
+        Self {
+            cells: [OFF; Frame::CAP],
+            len: 0,
+            next: 0,
+        }
+    }
+
+    fn pop(&mut self) -> Option<Rgb> {
+        let c = self.cells[..self.len].get(self.next).copied();
+        self.next += 1;
+        c
+    }
+
+    fn clear(&mut self) {
+        self.len = 0;
+        self.next = 0;
+    }
+
+    /// Append a cell (past the capacity they are dropped).
+    pub fn push(&mut self, c: Rgb) {
+        if self.len < Frame::CAP {
+            self.cells[self.len] = c;
+            self.len += 1;
+        }
+    }
+
+    /// Append `cells` idle cells.
+    pub fn push_gap(&mut self, cells: u32) {
+        for _ in 0..cells {
+            self.push(OFF);
+        }
+    }
+
+    /// Append the bits of `byte`, LSB first.
+    pub fn push_lsb_first(&mut self, byte: u8, one: Rgb, zero: Rgb) {
+        self.push_msb_first(byte.reverse_bits(), 8, one, zero);
+    }
+
+    /// Append the low `bits` bits of `byte`, MSB first.
+    pub fn push_msb_first(&mut self, byte: u8, bits: u32, one: Rgb, zero: Rgb) {
+        for k in (0..bits).rev() {
+            self.push(if (byte >> k) & 1 == 1 { one } else { zero });
+        }
+    }
+}
+
+/// A bus protocol's framing.
+pub trait Framing {
+    /// Lay out the next frame from `source` into `out` (left empty while the
+    /// source is idle), ending in `gaps.scale(source.gap())` idle cells.
+    fn frame<S: ByteSource>(&mut self, source: &mut S, gaps: &Gaps, out: &mut Frame);
+}
+
+/// UART 8N1, idle line between bytes.
+#[derive(Clone, Copy, Default)]
+pub struct Uart;
+
+impl Framing for Uart {
+    fn frame<S: ByteSource>(&mut self, source: &mut S, gaps: &Gaps, out: &mut Frame) {
+        let Some(byte) = source.next_byte() else {
+            return;
+        };
+        out.push(UART_START);
+        out.push_lsb_first(byte, BIT_ONE, BIT_ZERO);
+        out.push(UART_STOP);
+        out.push_gap(gaps.scale(source.gap()));
+    }
+}
+
+/// I2C transactions as [`Mark`](super::source::Mark)ed by a tap, else
+/// [`BYTES_PER_FRAME`] bytes each; laid out a byte at a time.
+#[derive(Clone, Copy, Default)]
+pub struct I2c {
+    /// The source marks transactions (a START was seen).
+    marked: bool,
+    /// Whether the open transaction reads; None between transactions.
+    open: Option<bool>,
+    /// Data bytes in the open transaction.
+    bytes: usize,
+}
+
+impl I2c {
+    pub const fn new() -> Self {
+        Self {
+            marked: false,
+            open: None,
+            bytes: 0,
+        }
+    }
+
+    /// (Repeated) START and the address + R/W byte `a`.
+    fn address(&mut self, out: &mut Frame, a: u8) {
+        let read = a & 1 == 1;
+        out.push(I2C_START);
+        out.push_msb_first(a >> 1, 7, I2C_ADDR_ONE, I2C_ADDR_ZERO);
+        out.push(if read { I2C_READ } else { I2C_WRITE });
+        out.push(I2C_ACK);
+        self.open = Some(read);
+    }
+
+    fn stop<S: ByteSource>(&mut self, source: &mut S, gaps: &Gaps, out: &mut Frame) {
+        out.push(I2C_STOP);
+        out.push_gap(gaps.scale(source.gap()));
+        self.open = None;
+        self.bytes = 0;
+    }
+}
+
+impl Framing for I2c {
+    fn frame<S: ByteSource>(&mut self, source: &mut S, gaps: &Gaps, out: &mut Frame) {
+        let Some((b, mark)) = source.next_marked() else {
+            // Unmarked transactions close when the source runs dry; marked
+            // ones wait for their STOP.
+            if self.open.is_some() && !self.marked {
+                self.stop(source, gaps, out);
+            }
+            return;
+        };
+        self.marked |= mark.start;
+        match self.open {
+            // Lapped into the middle of a transaction: skip to the next START.
+            None if self.marked && !mark.start => return,
+            None => self.address(out, b),
+            Some(_) if mark.start => self.address(out, b),
+            Some(read) => {
+                self.bytes += 1;
+                out.push_msb_first(b, 8, BIT_ONE, BIT_ZERO);
+                // The master NACKs the last byte it reads.
+                let last = mark.last || (!self.marked && self.bytes == BYTES_PER_FRAME);
+                out.push(if read && last { I2C_NACK } else { I2C_ACK });
+            }
+        }
+        if mark.stop || (!self.marked && self.bytes == BYTES_PER_FRAME) {
+            self.stop(source, gaps, out);
+        }
+    }
+}
+
+/// SPI mode 0, MSB first: chip select asserted around [`BYTES_PER_FRAME`]
+/// bytes, a clock cell before every data bit.
+#[derive(Clone, Copy, Default)]
+pub struct Spi;
+
+impl Framing for Spi {
+    fn frame<S: ByteSource>(&mut self, source: &mut S, gaps: &Gaps, out: &mut Frame) {
+        let Some(first) = source.next_byte() else {
+            return;
+        };
+        out.push(SPI_CS);
+        for i in 0..BYTES_PER_FRAME {
+            let byte = if i == 0 {
+                Some(first)
+            } else {
+                source.next_byte()
+            };
+            let Some(b) = byte else { break };
+            for k in (0..8).rev() {
+                out.push(SPI_CLK);
+                out.push(if (b >> k) & 1 == 1 { BIT_ONE } else { BIT_ZERO });
+            }
+        }
+        out.push(SPI_CS);
+        out.push_gap(gaps.scale(source.gap()));
+    }
+}
+
+/// Bytes from a [`ByteSource`] scrolling down the strip framed by `F`.
+pub struct Framed<F, S = Random> {
+    lane: Lane,
+    framing: F,
+    source: S,
+    frame: Frame,
+    gaps: Gaps,
+}
+
+impl<F: Framing> Framed<F> {
+    /// Random bytes framed by `framing` on a strip of `len` LEDs.
+    pub fn new(len: usize, framing: F) -> Self {
+        Self::with_source(len, framing, Random::default())
+    }
+}
+
+impl<F: Framing, S: ByteSource> Framed<F, S> {
+    /// The bytes from `source` framed by `framing`.
+    pub fn with_source(len: usize, framing: F, source: S) -> Self {
+        Self {
+            lane: Lane::new(len),
+            framing,
+            source,
+            frame: Frame::new(),
+            gaps: Gaps::default(),
+        }
+    }
+
+    /// Leave a trail fading with time constant `tau_ms`.
+    pub fn with_trail(mut self, tau_ms: u32) -> Self {
+        self.lane.set_trail(tau_ms);
+        self
+    }
+}
+
+fn next_cell<F: Framing, S: ByteSource>(
+    frame: &mut Frame,
+    framing: &mut F,
+    source: &mut S,
+    gaps: &Gaps,
+) -> Rgb {
+    if let Some(c) = frame.pop() {
+        return c;
+    }
+    frame.clear();
+    framing.frame(source, gaps, frame);
+    frame.pop().unwrap_or(OFF) // idle
+}
+
+impl<F: Framing, S: ByteSource> Effect for Framed<F, S> {
+    fn tick(&mut self, ctx: &Ctx) {
+        let (frame, framing) = (&mut self.frame, &mut self.framing);
+        let (source, gaps) = (&mut self.source, &self.gaps);
+        self.lane
+            .tick(ctx, || next_cell(frame, framing, source, gaps));
+    }
+
+    fn render(&self, fb: &mut Framebuffer) {
+        self.lane.render(fb);
+    }
+
+    fn params(&self) -> &'static [Param] {
+        Gaps::PARAMS
+    }
+
+    fn param(&self, index: usize) -> i32 {
+        self.gaps.param(index)
+    }
+
+    fn set_param(&mut self, index: usize, value: i32) {
+        self.gaps.set_param(index, value);
+    }
+}
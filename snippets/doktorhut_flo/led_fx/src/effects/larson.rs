@@ -0,0 +1,106 @@
+//! Larson scanner: an eye sweeping end to end at the LED speed with a fading
+//! trail, in the palette colour of where it is along the strip.
+
+use super::motion::draw_cell;
+use super::{Ctx, Effect};
+use crate::color::scale;
+use crate::palette::{Palette, Themed};
+use crate::Framebuffer;
+
+pub struct Larson {
+    palette: Themed,
+    width: i32,
+    decay_q16: u32,
+    pos_q8: i32,
+    forward: bool,
+    len: usize,
+}
+
+impl Larson {
+    pub fn new() -> Self {
+        Self {
+            palette: Themed::new(),
+            width: 3,
+            decay_q16: 32768,
+            pos_q8: 0,
+            forward: true,
+            len: 0,
+        }
+    }
+
+    /// Always use `palette` instead of the theme.
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette.pin(palette);
+        self
+    }
+
+    /// Eye of `width` LEDs; each trail LED keeps `decay_q16` of the one
+    /// before (default 3 LEDs, 0.5).
+    pub fn with_eye(mut self, width: u32, decay_q16: u32) -> Self {
+        self.width = width.max(1) as i32;
+        self.decay_q16 = decay_q16.min(65280);
+        self
+    }
+
+    /// Last position the eye's leading LED reaches (Q8.8).
+    fn end_q8(&self) -> i32 {
+        (self.len as i32 - self.width).max(0) * 256
+    }
+}
+
+impl Default for Larson {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Effect for Larson {
+    fn tick(&mut self, ctx: &Ctx) {
+        self.palette.resolve(ctx);
+        self.len = ctx.len;
+        let end = self.end_q8();
+        let step = ctx.advance_q8(ctx.velocity_q8).max(1);
+        if self.forward {
+            self.pos_q8 += step;
+        } else {
+            self.pos_q8 -= step;
+        }
+        // Bounce off both ends.
+        if self.pos_q8 >= end {
+            self.pos_q8 = (2 * end - self.pos_q8).max(0);
+            self.forward = false;
+        } else if self.pos_q8 <= 0 {
+            self.pos_q8 = (-self.pos_q8).min(end);
+            self.forward = true;
+        }
+    }
+
+    fn render(&self, fb: &mut Framebuffer) {
+        fb.fill([0; 3]);
+        let len = fb.len().min(self.len);
+        let end = self.end_q8();
+        let index = if end > 0 {
+            (self.pos_q8 * 255 / end) as u8
+        } else {
+            0
+        };
+        let c = self.palette.at(index);
+        let fb = &mut fb[..len];
+        for k in 0..self.width {
+            draw_cell(fb, self.pos_q8 + k * 256, c);
+        }
+        // Trail on the side it came from.
+        let (back, first) = if self.forward {
+            (-256, self.pos_q8 - 256)
+        } else {
+            (256, self.pos_q8 + self.width * 256)
+        };
+        let mut level = self.decay_q16;
+        let mut pos = first;
+        while level >= 256 && (-256..len as i32 * 256).contains(&pos) {
+            draw_cell(fb, pos, scale(c, level));
+            level = (level * self.decay_q16) >> 16;
+            pos += back;
+        }
+    }
+}
@@ -0,0 +1,151 @@
+mod beer;
+mod breathe;
+mod chase;
+mod comet;
+mod fire;
+pub mod framing;
+mod larson;
+mod motion;
+pub mod occupancy;
+mod packet;
+mod party;
+mod plasma;
+mod plasma2d;
+pub mod source;
+pub mod spectrum;
+mod stream;
+mod twinkle;
+
+pub use beer::BeerByte;
+pub use breathe::Breathe;
+pub use chase::Chase;
+pub use comet::Comet;
+pub use fire::Fire;
+pub use framing::Framed;
+pub use larson::Larson;
+pub use occupancy::Occupancy;
+pub use packet::Packet;
+pub use party::Party;
+pub use plasma::Plasma;
+pub use plasma2d::Plasma2d;
+pub use source::ByteSource;
+pub use spectrum::Spectrum;
+pub use stream::Stream;
+pub use twinkle::Twinkle;
+
+use crate::audio::Audio;
+use crate::palette::Palette;
+use crate::tempo::Beat;
+use crate::Framebuffer;
+
+/// Velocities are given in Q8.8 LEDs per reference frame of this many ms (the
+/// original fixed frame time), whatever the actual frame time is.
+pub const REF_FRAME_MS: u32 = 8;
+
+/// Per-frame inputs for effects: the clock plus a snapshot of the `control`
+/// values they react to, so effects never read globals themselves.
+#[derive(Clone, Copy, Default)]
+pub struct Ctx {
+    /// Wall-clock time (ms) and time since the previous tick.
+    pub now_ms: u32,
+    pub dt_ms: u32,
+    /// Strip length (LEDs).
+    pub len: usize,
+    /// LED speed, Q8.8 LEDs per `REF_FRAME_MS`.
+    pub velocity_q8: u32,
+    /// IMU roll (deg) and acceleration (g).
+    pub roll: i32,
+    pub accel: [f32; 3],
+    /// The rig's colour theme (see [`crate::palette`]).
+    pub palette: Palette,
+    /// The music playing (see [`crate::audio`]).
+    pub audio: Audio,
+    /// Where the tempo clock is, if one is set (see [`crate::tempo`]).
+    pub beat: Option<Beat>,
+}
+
+impl Ctx {
+    /// Distance (Q8.8 LEDs) travelled at `velocity_q8` during this tick.
+    pub fn advance_q8(&self, velocity_q8: u32) -> i32 {
+        (velocity_q8 * self.dt_ms / REF_FRAME_MS) as i32
+    }
+}
+
+/// A tunable effect parameter, for settings menus: whole numbers from `min`
+/// to `max` in steps of `step`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Param {
+    pub name: &'static str,
+    pub min: i32,
+    pub max: i32,
+    pub step: i32,
+}
+
+impl Param {
+    pub const fn new(name: &'static str, min: i32, max: i32, step: i32) -> Self {
+        Self {
+            name,
+            min,
+            max,
+            step,
+        }
+    }
+
+    /// `value` clamped to the range.
+    pub fn clamp(&self, value: i32) -> i32 {
+        value.clamp(self.min, self.max)
+    }
+
+    /// `value` one step up (or down), staying in the range.
+    pub fn stepped(&self, value: i32, up: bool) -> i32 {
+        let step = if up { self.step } else { -self.step };
+        self.clamp(value.saturating_add(step))
+    }
+}
+
+/// How an effect's pixels combine with the frame underneath.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Compose {
+    /// Paints every pixel.
+    Replace,
+    /// Paints only the pixels it covers (leaving the rest black); the
+    /// compositor lets the layers below show through those.
+    Overlay,
+}
+
+pub trait Effect {
+    /// Advance the animation by `ctx.dt_ms`.
+    fn tick(&mut self, ctx: &Ctx);
+
+    /// Draw the current state into `fb`.
+    fn render(&self, fb: &mut Framebuffer);
+
+    /// The effect becomes active (shown from this frame on).
+    fn on_enter(&mut self, _ctx: &Ctx) {}
+
+    /// The effect stops being shown.
+    fn on_exit(&mut self) {}
+
+    /// One-shot effects report when they are done; looping ones never are.
+    fn is_finished(&self) -> bool {
+        false
+    }
+
+    fn compose(&self) -> Compose {
+        Compose::Replace
+    }
+
+    /// Tunable parameters; [`param`](Effect::param) and
+    /// [`set_param`](Effect::set_param) index into this list.
+    fn params(&self) -> &'static [Param] {
+        &[]
+    }
+
+    /// Current value of parameter `index` (0 if there is none).
+    fn param(&self, _index: usize) -> i32 {
+        0
+    }
+
+    /// Set parameter `index`, clamped to its range; other indices are ignored.
+    fn set_param(&mut self, _index: usize, _value: i32) {}
+}
@@ -0,0 +1,113 @@
+//! Smooth motion for the travelling-bit effects: cells drawn at their Q8.8
+//! position split between the two LEDs they overlap, a conveyor of cells
+//! scrolling down the strip, and an optional trail that fades behind whatever
+//! moves.
+
+use libm::expf;
+
+use super::{Ctx, REF_FRAME_MS};
+use crate::color::scale;
+use crate::{Framebuffer, Rgb, MAX_LEDS};
+
+/// Add one LED-wide cell of colour `c` at Q8.8 position `pos_q8`: LED
+/// `pos >> 8` gets the part not yet moved on, the next LED the rest. Cells
+/// next to each other add up to their interpolated colour.
+pub fn draw_cell(fb: &mut Framebuffer, pos_q8: i32, c: Rgb) {
+    let idx = pos_q8 >> 8;
+    let frac = (pos_q8 & 0xFF) as u32;
+    for (i, weight) in [(idx, 256 - frac), (idx + 1, frac)] {
+        if weight == 0 || i < 0 || i >= fb.len() as i32 {
+            continue;
+        }
+        let px = &mut fb[i as usize];
+        for (p, &v) in px.iter_mut().zip(c.iter()) {
+            *p = p.saturating_add((v as u32 * weight / 256) as u16);
+        }
+    }
+}
+
+/// Cells entering at the data-in end and scrolling down the strip: the first
+/// cell pushed leads.
+pub struct Scroll {
+    cells: [Rgb; MAX_LEDS],
+    len: usize,
+    pos_q8: i32,
+}
+
+impl Scroll {
+    /// A dark strip of `len` cells (at most `MAX_LEDS`).
+    pub fn new(len: usize) -> Self {
+        Self {
+            cells: [[0; 3]; MAX_LEDS],
+            len: len.min(MAX_LEDS),
+            pos_q8: 0,
+        }
+    }
+
+    /// Move on by this tick's distance; returns how many cells to `push`.
+    pub fn advance(&mut self, ctx: &Ctx) -> u32 {
+        self.pos_q8 += ctx.advance_q8(ctx.velocity_q8).max(1);
+        let steps = self.pos_q8 >> 8;
+        self.pos_q8 &= 0xFF;
+        steps as u32
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Shift every cell one LED on and put `c` at the start.
+    pub fn push(&mut self, c: Rgb) {
+        if self.len > 0 {
+            self.cells.copy_within(..self.len - 1, 1);
+            self.cells[0] = c;
+        }
+    }
+
+    /// Cell `i` sits `pos_q8` of the way from LED `i` to the next one.
+    pub fn render(&self, fb: &mut Framebuffer) {
+        let n = fb.len().min(self.len);
+        fb[..n].fill([0; 3]);
+        for (i, &c) in self.cells[..n].iter().enumerate() {
+            draw_cell(&mut fb[..n], i as i32 * 256 + self.pos_q8, c);
+        }
+    }
+}
+
+/// A motion-blur trail that fades with time constant `tau_ms`, left in the
+/// dark cells behind whatever moves (so a packet's zero bits stay as drawn).
+#[derive(Clone, Copy)]
+pub struct Trail {
+    tau_ms: u32,
+    /// Share (Q16) of a pixel left on the LED behind it; set per tick.
+    keep_q16: u32,
+}
+
+impl Trail {
+    pub fn new(tau_ms: u32) -> Self {
+        Self {
+            tau_ms: tau_ms.max(1),
+            keep_q16: 0,
+        }
+    }
+
+    /// Moving at `velocity_q8`, an LED is passed every `ms_per_led`; the trail
+    /// left there has faded by `exp(-ms_per_led / tau)`.
+    pub fn update(&mut self, velocity_q8: u32) {
+        let ms_per_led = (REF_FRAME_MS * 256) as f32 / velocity_q8.max(1) as f32;
+        self.keep_q16 = (expf(-ms_per_led / self.tau_ms as f32) * 65535.0) as u32;
+    }
+
+    /// Fade each lit run backwards (towards the data-in end) into the dark
+    /// pixels behind it; lit pixels are left alone. Pass only the part of the
+    /// frame up to a packet's tail to keep its inner gaps clear.
+    pub fn apply(&self, fb: &mut Framebuffer) {
+        let mut tail: Rgb = [0; 3];
+        for px in fb.iter_mut().rev() {
+            if *px == [0; 3] {
+                *px = scale(tail, self.keep_q16);
+            }
+            tail = *px;
+        }
+    }
+}
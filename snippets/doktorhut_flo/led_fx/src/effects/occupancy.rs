@@ -0,0 +1,87 @@
+//! An occupancy grid (which cells are full, e.g. the fluid sim's water) on a
+//! matrix, scaled to fit: each LED lights by how much of its patch of the
+//! grid is full. Colour follows depth in the grid through the palette, so the
+//! surface and the bottom differ.
+
+use super::{Ctx, Effect};
+use crate::color::scale;
+use crate::matrix::{Canvas, XyMap};
+use crate::palette::{Palette, Themed};
+use crate::Framebuffer;
+
+/// Largest grid: columns (bits of a row) and rows.
+pub const GRID_MAX: usize = 32;
+
+pub struct Occupancy {
+    map: XyMap,
+    palette: Themed,
+    cols: usize,
+    rows: usize,
+    /// Bit x of `grid[y]` set = cell (x, y) full.
+    grid: [u32; GRID_MAX],
+}
+
+impl Occupancy {
+    pub fn new(map: XyMap) -> Self {
+        Self {
+            map,
+            palette: Themed::new(),
+            cols: 0,
+            rows: 0,
+            grid: [0; GRID_MAX],
+        }
+    }
+
+    /// Always use `palette` instead of the theme.
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette.pin(palette);
+        self
+    }
+
+    /// Show a grid `cols` wide, one bitmask per row, top row first (both
+    /// clamped to [`GRID_MAX`]).
+    pub fn set_grid(&mut self, cols: usize, rows: &[u32]) {
+        self.cols = cols.min(GRID_MAX);
+        self.rows = rows.len().min(GRID_MAX);
+        self.grid[..self.rows].copy_from_slice(&rows[..self.rows]);
+    }
+
+    /// Cells of the grid under matrix column or row `i` of `n`: at least one,
+    /// so a grid smaller than the matrix is stretched.
+    fn patch(i: usize, n: usize, cells: usize) -> (usize, usize) {
+        let start = i * cells / n;
+        (start, ((i + 1) * cells / n).max(start + 1))
+    }
+}
+
+impl Effect for Occupancy {
+    fn tick(&mut self, ctx: &Ctx) {
+        self.palette.resolve(ctx);
+    }
+
+    fn render(&self, fb: &mut Framebuffer) {
+        if self.cols == 0 || self.rows == 0 {
+            return;
+        }
+        let mut canvas = Canvas::new(self.map, fb);
+        let (w, h) = (canvas.width(), canvas.height());
+        for y in 0..h {
+            let (gy0, gy1) = Self::patch(y, h, self.rows);
+            let color = self
+                .palette
+                .at((gy0 * 255 / self.rows.max(2).saturating_sub(1)) as u8);
+            for x in 0..w {
+                let (gx0, gx1) = Self::patch(x, w, self.cols);
+                let mask = (((1u64 << (gx1 - gx0)) - 1) << gx0) as u32;
+                let full: u32 = self.grid[gy0..gy1]
+                    .iter()
+                    .map(|row| (row & mask).count_ones())
+                    .sum();
+                let cells = ((gx1 - gx0) * (gy1 - gy0)) as u32;
+                if full > 0 {
+                    canvas.set(x, y, scale(color, full * 65535 / cells));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! PARTY: the theme's colours (a rainbow, by default) scrolling fast, with a
+//! tilt-driven overlay. The BEER strip shows a bright white "gravity ball"
+//! (framed by an off-LED on each side for pop) rolling to the downhill end; the
+//! other strips show a "liquid level" that fills up to the tilt angle. Shake
+//! (accel) blends everything white.
+
+use libm::{fabsf, sqrtf};
+
+use super::{Ctx, Effect, Param};
+use crate::color::scale;
+use crate::palette::Palette;
+use crate::Framebuffer;
+
+const BALL_R: i32 = 4; // solid white blob half-width
+const BALL_GAP: i32 = 2; // off-LEDs framing each side
+const BASE_Q16: u32 = 56540; // palette colours at 220/255
+const SCROLL_DEFAULT: u32 = 125; // palette steps per second
+
+pub struct Party {
+    palette: Palette,
+    hue_off: u32,
+    ball: bool,
+    scroll_rate: u32,
+    // Scroll position in thousandths of a palette step.
+    scroll_milli: u32,
+    roll: i32,
+    strobe: u32,
+}
+
+impl Party {
+    /// `hue_off` staggers the rainbow between strips; `ball` picks the gravity
+    /// ball overlay instead of the liquid level.
+    pub fn new(hue_off: u8, ball: bool) -> Self {
+        Self {
+            palette: Palette::default(),
+            hue_off: hue_off as u32,
+            ball,
+            scroll_rate: SCROLL_DEFAULT,
+            scroll_milli: 0,
+            roll: 0,
+            strobe: 0,
+        }
+    }
+
+    /// Tunable as the scroll speed, palette steps per second.
+    pub const PARAMS: &'static [Param] = &[Param::new("Scroll", 0, 500, 25)];
+}
+
+impl Effect for Party {
+    fn tick(&mut self, ctx: &Ctx) {
+        self.palette = ctx.palette;
+        self.scroll_milli = (self.scroll_milli + ctx.dt_ms * self.scroll_rate) % (256 * 1000);
+        self.roll = ctx.roll.clamp(-90, 90);
+        // Shake -> white blend.
+        let [ax, ay, az] = ctx.accel;
+        let mag = fabsf(sqrtf(ax * ax + ay * ay + az * az) - 1.0);
+        self.strobe = (mag.clamp(0.0, 1.0) * 255.0) as u32; // 0..255
+    }
+
+    fn render(&self, fb: &mut Framebuffer) {
+        let scroll = self.scroll_milli / 1000;
+        // Tilt (roll -90..90) -> a position along the strip.
+        let len = fb.len();
+        let pos = (self.roll + 90) * (len as i32 - 1) / 180; // 0..len-1
+        let strobe = self.strobe;
+
+        for (i, px) in fb.iter_mut().enumerate() {
+            let hue = (scroll + self.hue_off + (i as u32 * 256 / len as u32)) as u8;
+            let base = scale(self.palette.at(hue), BASE_Q16);
+            let idx = i as i32;
+
+            // White amount (0..255) and brightness (0..255) from the tilt overlay.
+            let (white, bright) = if self.ball {
+                let d = (idx - pos).abs();
+                if d <= BALL_R {
+                    (255, 255) // solid bright-white ball (high contrast)
+                } else if d <= BALL_R + BALL_GAP {
+                    (0, 0) // dark frame on each side so the ball stands out
+                } else {
+                    (0, 14) // very dim rainbow background
+                }
+            } else if idx <= pos {
+                (0, 255) // liquid: bright up to the level
+            } else {
+                (0, 45) // liquid: dim above
+            };
+            let bright = bright.max(strobe); // a hard shake lights the whole strip
+            let w = white.max(strobe);
+
+            // Global brightness is applied by the driver at encode time.
+            let mix = |c: u16| -> u16 {
+                let lit = c as u32 * bright / 255; // apply brightness
+                let lifted = lit + (65535 - lit) * w / 255; // blend toward white
+                lifted as u16
+            };
+            *px = base.map(mix);
+        }
+    }
+
+    fn params(&self) -> &'static [Param] {
+        Self::PARAMS
+    }
+
+    fn param(&self, index: usize) -> i32 {
+        match index {
+            0 => self.scroll_rate as i32,
+            _ => 0,
+        }
+    }
+
+    fn set_param(&mut self, index: usize, value: i32) {
+        if index == 0 {
+            self.scroll_rate = Self::PARAMS[0].clamp(value) as u32;
+        }
+    }
+}
@@ -0,0 +1,67 @@
+//! Plasma: three sine waves of different lengths and speeds summed along the
+//! strip, the sum picking the palette colour. Slow, organic colour flow.
+
+use core::f32::consts::TAU;
+
+use libm::sinf;
+
+use super::{Ctx, Effect};
+use crate::palette::{Palette, Themed};
+use crate::Framebuffer;
+
+pub struct Plasma {
+    palette: Themed,
+    wavelength: f32,
+    period_ms: u32,
+    t_ms: u32,
+}
+
+impl Plasma {
+    pub fn new() -> Self {
+        Self {
+            palette: Themed::new(),
+            wavelength: 24.0,
+            period_ms: 6000,
+            t_ms: 0,
+        }
+    }
+
+    /// Always use `palette` instead of the theme.
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette.pin(palette);
+        self
+    }
+
+    /// Main wave `wavelength` LEDs long, drifting one wavelength per
+    /// `period_ms` (default 24 LEDs, 6 s).
+    pub fn with_wave(mut self, wavelength: u32, period_ms: u32) -> Self {
+        self.wavelength = wavelength.max(1) as f32;
+        self.period_ms = period_ms.max(1);
+        self
+    }
+}
+
+impl Default for Plasma {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Effect for Plasma {
+    fn tick(&mut self, ctx: &Ctx) {
+        self.palette.resolve(ctx);
+        // Wrap on a whole number of periods of all three waves.
+        self.t_ms = (self.t_ms + ctx.dt_ms) % (self.period_ms * 10);
+    }
+
+    fn render(&self, fb: &mut Framebuffer) {
+        let t = self.t_ms as f32 / self.period_ms as f32 * TAU;
+        for (i, px) in fb.iter_mut().enumerate() {
+            let x = i as f32 / self.wavelength * TAU;
+            let v = sinf(x - t) + sinf(x * 0.5 + t * 0.7) + sinf(x * 1.7 - t * 1.3 + 1.0);
+            // -3..3 -> 0..255
+            let index = ((v + 3.0) * (255.0 / 6.0)) as u8;
+            *px = self.palette.at(index);
+        }
+    }
+}
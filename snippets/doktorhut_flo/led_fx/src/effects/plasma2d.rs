@@ -0,0 +1,74 @@
+//! Plasma on a matrix: sine waves across x, down y, along the diagonal and
+//! out from the centre, summed into the palette colour. The 2D cousin of
+//! [`Plasma`](super::Plasma).
+
+use core::f32::consts::TAU;
+
+use libm::{sinf, sqrtf};
+
+use super::{Ctx, Effect};
+use crate::matrix::{Canvas, XyMap};
+use crate::palette::{Palette, Themed};
+use crate::Framebuffer;
+
+pub struct Plasma2d {
+    map: XyMap,
+    palette: Themed,
+    wavelength: f32,
+    period_ms: u32,
+    t_ms: u32,
+}
+
+impl Plasma2d {
+    pub fn new(map: XyMap) -> Self {
+        Self {
+            map,
+            palette: Themed::new(),
+            wavelength: 12.0,
+            period_ms: 6000,
+            t_ms: 0,
+        }
+    }
+
+    /// Always use `palette` instead of the theme.
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette.pin(palette);
+        self
+    }
+
+    /// Main waves `wavelength` LEDs long, drifting one wavelength per
+    /// `period_ms` (default 12 LEDs, 6 s).
+    pub fn with_wave(mut self, wavelength: u32, period_ms: u32) -> Self {
+        self.wavelength = wavelength.max(1) as f32;
+        self.period_ms = period_ms.max(1);
+        self
+    }
+}
+
+impl Effect for Plasma2d {
+    fn tick(&mut self, ctx: &Ctx) {
+        self.palette.resolve(ctx);
+        // Wrap on a whole number of periods of all four waves.
+        self.t_ms = (self.t_ms + ctx.dt_ms) % (self.period_ms * 10);
+    }
+
+    fn render(&self, fb: &mut Framebuffer) {
+        let t = self.t_ms as f32 / self.period_ms as f32 * TAU;
+        let k = TAU / self.wavelength;
+        let (cx, cy) = (self.map.width as f32 / 2.0, self.map.height as f32 / 2.0);
+        let mut canvas = Canvas::new(self.map, fb);
+        for y in 0..canvas.height() {
+            for x in 0..canvas.width() {
+                let (fx, fy) = (x as f32, y as f32);
+                let r = sqrtf((fx - cx) * (fx - cx) + (fy - cy) * (fy - cy));
+                let v = sinf(fx * k - t)
+                    + sinf(fy * k * 0.8 + t * 0.7)
+                    + sinf((fx + fy) * k * 0.6 - t * 1.3 + 1.0)
+                    + sinf(r * k * 1.2 - t * 0.9);
+                // -4..4 -> 0..255
+                let index = ((v + 4.0) * (255.0 / 8.0)) as u8;
+                canvas.set(x, y, self.palette.at(index));
+            }
+        }
+    }
+}
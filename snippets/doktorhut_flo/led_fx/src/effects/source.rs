@@ -0,0 +1,300 @@
+//! Where [`Stream`](super::Stream) gets its bytes: random filler, a fixed
+//! message, or a tap of real bus traffic.
+
+use core::sync::atomic::{AtomicU32, AtomicU8, Ordering};
+
+use super::Param;
+
+/// OFF LEDs between bytes.
+pub const GAP_MIN: u32 = 2;
+pub const GAP_MAX: u32 = 8;
+
+/// Where a byte sits in its bus transaction, for sources that know (a
+/// [`ByteTap`] of I2C traffic); see [`I2c`](super::framing::I2c).
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Mark {
+    /// Address + R/W byte after a START or repeated START.
+    pub start: bool,
+    /// Last byte before the next repeated START or the STOP.
+    pub last: bool,
+    /// A STOP follows.
+    pub stop: bool,
+}
+
+impl Mark {
+    const START: u8 = 1;
+    const LAST: u8 = 2;
+    const STOP: u8 = 4;
+
+    fn bits(self) -> u8 {
+        (self.start as u8 * Self::START)
+            | (self.last as u8 * Self::LAST)
+            | (self.stop as u8 * Self::STOP)
+    }
+
+    fn from_bits(bits: u8) -> Self {
+        Self {
+            start: bits & Self::START != 0,
+            last: bits & Self::LAST != 0,
+            stop: bits & Self::STOP != 0,
+        }
+    }
+}
+
+pub trait ByteSource {
+    /// The next byte to send, or None if there is nothing to send right now
+    /// (the stream idles and asks again on the next LED).
+    fn next_byte(&mut self) -> Option<u8>;
+
+    /// The next byte and its place in a bus transaction. Sources that know
+    /// nothing of transactions leave it unmarked.
+    fn next_marked(&mut self) -> Option<(u8, Mark)> {
+        self.next_byte().map(|b| (b, Mark::default()))
+    }
+
+    /// OFF LEDs after the byte just sent.
+    fn gap(&mut self) -> u32 {
+        GAP_MIN
+    }
+}
+
+/// How long an effect draws its source's gaps: a source gap of
+/// `GAP_MIN..=GAP_MAX` is stretched onto `min..=max`, so the source still
+/// says short or long and the user picks how long that is.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Gaps {
+    pub min: u32,
+    pub max: u32,
+}
+
+impl Gaps {
+    /// Tunable as `min`, `max` (either may be the longer one).
+    pub const PARAMS: &'static [Param] = &[
+        Param::new("Gap min", 1, 16, 1),
+        Param::new("Gap max", 1, 32, 1),
+    ];
+
+    /// OFF LEDs for the source's `gap`.
+    pub fn scale(&self, gap: u32) -> u32 {
+        let g = gap.clamp(GAP_MIN, GAP_MAX) - GAP_MIN;
+        let (lo, hi) = (self.min.min(self.max), self.min.max(self.max));
+        lo + (hi - lo) * g / (GAP_MAX - GAP_MIN)
+    }
+
+    pub fn param(&self, index: usize) -> i32 {
+        match index {
+            0 => self.min as i32,
+            1 => self.max as i32,
+            _ => 0,
+        }
+    }
+
+    pub fn set_param(&mut self, index: usize, value: i32) {
+        match index {
+            0 => self.min = Self::PARAMS[0].clamp(value) as u32,
+            1 => self.max = Self::PARAMS[1].clamp(value) as u32,
+            _ => {}
+        }
+    }
+}
+
+impl Default for Gaps {
+    /// The source's own gaps.
+    fn default() -> Self {
+        Self {
+            min: GAP_MIN,
+            max: GAP_MAX,
+        }
+    }
+}
+
+/// Random bytes with random gaps (xorshift32, so a given seed always gives
+/// the same stream).
+pub struct Random(u32);
+
+impl Random {
+    /// `seed` must not be 0.
+    pub const fn new(seed: u32) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.0 = x;
+        x
+    }
+
+    /// Uniform-ish in `lo..=hi`.
+    pub fn range(&mut self, lo: u32, hi: u32) -> u32 {
+        lo + self.next_u32() % (hi - lo + 1)
+    }
+}
+
+impl Default for Random {
+    fn default() -> Self {
+        Self::new(0x1234_5678)
+    }
+}
+
+impl ByteSource for Random {
+    fn next_byte(&mut self) -> Option<u8> {
+        Some(self.next_u32() as u8)
+    }
+
+    fn gap(&mut self) -> u32 {
+        self.range(GAP_MIN, GAP_MAX)
+    }
+}
+
+/// A message sent over and over, with a longer pause after each repeat.
+pub struct Text {
+    msg: &'static [u8],
+    next: usize,
+}
+
+impl Text {
+    pub const fn new(msg: &'static [u8]) -> Self {
+        Self { msg, next: 0 }
+    }
+}
+
+impl ByteSource for Text {
+    fn next_byte(&mut self) -> Option<u8> {
+        let b = *self.msg.get(self.next)?;
+        self.next += 1;
+        Some(b)
+    }
+
+    fn gap(&mut self) -> u32 {
+        if self.next < self.msg.len() {
+            return GAP_MIN;
+        }
+        self.next = 0;
+        GAP_MAX
+    }
+}
+
+/// Lock-free ring of the last `N` bytes seen on a bus (with their
+/// [`Mark`]s). Writers never wait: the oldest bytes are overwritten, and a
+/// reader that falls behind skips ahead. One writer at a time (tasks on one
+/// executor take turns); any number of readers, each with its own
+/// [`TapReader`].
+pub struct ByteTap<const N: usize> {
+    buf: [AtomicU8; N],
+    marks: [AtomicU8; N],
+    /// Bytes written so far (wrapping); the next goes to `head % N`.
+    head: AtomicU32,
+}
+
+impl<const N: usize> ByteTap<N> {
+    pub const fn new() -> Self {
+        Self {
+            buf: [const { AtomicU8::new(0) }; N],
+            marks: [const { AtomicU8::new(0) }; N],
+            head: AtomicU32::new(0),
+        }
+    }
+
+    /// Unmarked bytes.
+    pub fn push(&self, bytes: &[u8]) {
+        for &b in bytes {
+            self.push_marked(b, Mark::default());
+        }
+    }
+
+    pub fn push_marked(&self, byte: u8, mark: Mark) {
+        let head = self.head.load(Ordering::Relaxed);
+        self.buf[head as usize % N].store(byte, Ordering::Relaxed);
+        self.marks[head as usize % N].store(mark.bits(), Ordering::Relaxed);
+        // Publish byte by byte so readers see each as soon as it is in.
+        self.head.store(head.wrapping_add(1), Ordering::Release);
+    }
+
+    /// One completed I2C transaction with `address`, as on the wire: the
+    /// address + R/W byte at the start and at every change of direction
+    /// (repeated START), the data bytes, then STOP. `ops` are
+    /// (read, data) in order.
+    pub fn push_i2c<'a>(&self, address: u8, ops: impl IntoIterator<Item = (bool, &'a [u8])>) {
+        // Each byte goes out once the next shows whether it was the last of
+        // its run.
+        let mut pending: Option<(u8, bool)> = None;
+        let mut emit = |byte: u8, start: bool| {
+            if let Some((b, s)) = pending.replace((byte, start)) {
+                let mark = Mark {
+                    start: s,
+                    last: start,
+                    stop: false,
+                };
+                self.push_marked(b, mark);
+            }
+        };
+        let mut reading = None;
+        for (read, data) in ops {
+            if reading != Some(read) {
+                emit(address << 1 | read as u8, true);
+                reading = Some(read);
+            }
+            for &b in data {
+                emit(b, false);
+            }
+        }
+        if let Some((b, s)) = pending {
+            let mark = Mark {
+                start: s,
+                last: true,
+                stop: true,
+            };
+            self.push_marked(b, mark);
+        }
+    }
+
+    /// A reader starting at the bytes pushed from now on.
+    pub fn reader(&'static self) -> TapReader<N> {
+        TapReader {
+            tap: self,
+            tail: self.head.load(Ordering::Acquire),
+        }
+    }
+}
+
+impl<const N: usize> Default for ByteTap<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct TapReader<const N: usize> {
+    tap: &'static ByteTap<N>,
+    /// Next byte to read, as a `head` count.
+    tail: u32,
+}
+
+impl<const N: usize> ByteSource for TapReader<N> {
+    fn next_byte(&mut self) -> Option<u8> {
+        self.next_marked().map(|(b, _)| b)
+    }
+
+    fn next_marked(&mut self) -> Option<(u8, Mark)> {
+        loop {
+            let head = self.tap.head.load(Ordering::Acquire);
+            if head == self.tail {
+                return None;
+            }
+            if head.wrapping_sub(self.tail) > N as u32 {
+                self.tail = head.wrapping_sub(N as u32); // lapped: skip ahead
+            }
+            let i = self.tail as usize % N;
+            let b = self.tap.buf[i].load(Ordering::Relaxed);
+            let mark = Mark::from_bits(self.tap.marks[i].load(Ordering::Relaxed));
+            // Still ours if the writer has not lapped it while we read.
+            let head = self.tap.head.load(Ordering::Acquire);
+            if head.wrapping_sub(self.tail) <= N as u32 {
+                self.tail = self.tail.wrapping_add(1);
+                return Some((b, mark));
+            }
+        }
+    }
+}
@@ -0,0 +1,192 @@
+//! The MUSIC strip's audio meter, driven by the analysed music in
+//! `ctx.audio` (see [`crate::audio`]): a VU bar with a peak dot that holds and
+//! falls, or the spectrum as one segment per band, bass at the data-in end.
+//! Colours come from the palette along the strip; every beat flashes it.
+//! Once the music has been quiet for a while the strip breathes instead, so
+//! the MUSIC show is never just dark.
+
+use super::{Ctx, Effect, Param};
+use crate::audio::BANDS;
+use crate::color::scale;
+use crate::palette::{Palette, Themed};
+use crate::Framebuffer;
+
+/// Values of the "Mode" parameter.
+pub const MODE_VU: i32 = 0;
+pub const MODE_SPECTRUM: i32 = 1;
+
+// A beat lifts the whole strip to this level (Q16), fading over BEAT_FLASH_MS.
+const BEAT_LIFT_Q16: u32 = 16384;
+const BEAT_FLASH_MS: u32 = 150;
+// The peak dot stays put this long before it falls.
+const PEAK_HOLD_MS: u32 = 500;
+// Silence this long (and an empty meter) before the strip starts breathing.
+const QUIET_MS: u32 = 2000;
+
+pub struct Spectrum {
+    palette: Themed,
+    mode: i32,
+    decay_ms: u32,
+    period_ms: u32,
+    // What is shown: jumps up with the music, falls at the decay rate.
+    bands: [u16; BANDS],
+    level: u16,
+    peak: u16,
+    peak_age_ms: u32,
+    flash_q16: u32,
+    quiet_ms: u32,
+    phase_ms: u32,
+}
+
+impl Spectrum {
+    /// Tunable as the mode ([`MODE_VU`] or [`MODE_SPECTRUM`]), the time
+    /// (ms) a full-scale bar takes to fall and the breathing period (ms) while
+    /// quiet.
+    pub const PARAMS: &'static [Param] = &[
+        Param::new("Mode", MODE_VU, MODE_SPECTRUM, 1),
+        Param::new("Decay", 100, 2000, 100),
+        Param::new("Period", 500, 8000, 250),
+    ];
+
+    pub fn new() -> Self {
+        Self {
+            palette: Themed::new(),
+            mode: MODE_VU,
+            decay_ms: 500,
+            period_ms: 4000,
+            bands: [0; BANDS],
+            level: 0,
+            peak: 0,
+            peak_age_ms: 0,
+            flash_q16: 0,
+            quiet_ms: 0,
+            phase_ms: 0,
+        }
+    }
+
+    /// Always use `palette` instead of the theme.
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette.pin(palette);
+        self
+    }
+
+    pub fn with_mode(mut self, mode: i32) -> Self {
+        self.mode = Self::PARAMS[0].clamp(mode);
+        self
+    }
+}
+
+impl Default for Spectrum {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Jump up to `new`, or fall by `fall` toward it.
+fn follow(shown: &mut u16, new: u16, fall: u32) {
+    *shown = new.max(shown.saturating_sub(fall.min(65535) as u16));
+}
+
+impl Effect for Spectrum {
+    fn on_enter(&mut self, _ctx: &Ctx) {
+        self.bands = [0; BANDS];
+        self.level = 0;
+        self.peak = 0;
+        self.quiet_ms = 0;
+    }
+
+    fn tick(&mut self, ctx: &Ctx) {
+        self.palette.resolve(ctx);
+        let fall = 65535 * ctx.dt_ms / self.decay_ms;
+        for (shown, &new) in self.bands.iter_mut().zip(ctx.audio.bands.iter()) {
+            follow(shown, new, fall);
+        }
+        follow(&mut self.level, ctx.audio.level, fall);
+        if self.level >= self.peak {
+            self.peak = self.level;
+            self.peak_age_ms = 0;
+        } else {
+            self.peak_age_ms = self.peak_age_ms.saturating_add(ctx.dt_ms);
+            if self.peak_age_ms > PEAK_HOLD_MS {
+                follow(&mut self.peak, self.level, fall);
+            }
+        }
+        let since = ctx.audio.since_beat_ms;
+        self.flash_q16 = if since < BEAT_FLASH_MS {
+            (BEAT_FLASH_MS - since) * BEAT_LIFT_Q16 / BEAT_FLASH_MS
+        } else {
+            0
+        };
+        if self.peak == 0 && self.bands.iter().all(|&b| b == 0) && self.flash_q16 == 0 {
+            self.quiet_ms = self.quiet_ms.saturating_add(ctx.dt_ms);
+        } else {
+            self.quiet_ms = 0;
+        }
+        // The breath starts from dark each time the music stops.
+        if self.quiet_ms < QUIET_MS {
+            self.phase_ms = 0;
+        } else {
+            self.phase_ms = (self.phase_ms + ctx.dt_ms) % self.period_ms;
+        }
+    }
+
+    fn render(&self, fb: &mut Framebuffer) {
+        let len = fb.len();
+        if len == 0 {
+            return;
+        }
+        let color = |i: usize| self.palette.at((i * 255 / (len - 1).max(1)) as u8);
+        if self.quiet_ms >= QUIET_MS {
+            // Triangle from dark to full over the period, as Breathe does.
+            let phase = (self.phase_ms as u64 * 131072 / self.period_ms as u64) as u32;
+            let level = if phase < 65536 { phase } else { 131071 - phase };
+            for (i, px) in fb.iter_mut().enumerate() {
+                *px = scale(color(i), level);
+            }
+            return;
+        }
+        if self.mode == MODE_SPECTRUM {
+            for b in 0..BANDS {
+                let c = self.palette.at((b * 255 / (BANDS - 1)) as u8);
+                let level = (self.bands[b] as u32).max(self.flash_q16);
+                fb[b * len / BANDS..(b + 1) * len / BANDS].fill(scale(c, level));
+            }
+            return;
+        }
+        // VU: the bar (its last LED partly lit) over the beat glow.
+        let lit_q8 = self.level as u32 * len as u32 * 256 / 65535;
+        for (i, px) in fb.iter_mut().enumerate() {
+            let cover = lit_q8.saturating_sub(i as u32 * 256).min(256);
+            *px = scale(color(i), (cover * 65535 / 256).max(self.flash_q16));
+        }
+        if self.peak > 0 {
+            let i = self.peak as usize * (len - 1) / 65535;
+            fb[i] = color(i);
+        }
+    }
+
+    fn params(&self) -> &'static [Param] {
+        Self::PARAMS
+    }
+
+    fn param(&self, index: usize) -> i32 {
+        match index {
+            0 => self.mode,
+            1 => self.decay_ms as i32,
+            2 => self.period_ms as i32,
+            _ => 0,
+        }
+    }
+
+    fn set_param(&mut self, index: usize, value: i32) {
+        match index {
+            0 => self.mode = Self::PARAMS[0].clamp(value),
+            1 => self.decay_ms = Self::PARAMS[1].clamp(value) as u32,
+            2 => {
+                self.period_ms = Self::PARAMS[2].clamp(value) as u32;
+                self.phase_ms %= self.period_ms;
+            }
+            _ => {}
+        }
+    }
+}
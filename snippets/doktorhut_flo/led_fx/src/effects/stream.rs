@@ -0,0 +1,145 @@
+//! Continuous byte stream scrolling down the wire, MSB first, with OFF gaps
+//! between bytes; the bytes (and gaps) come from a [`ByteSource`]. Cells
+//! scroll in from the left at the shared velocity, drawn at their sub-LED
+//! position. Raw bits only; [`super::framing`] shows them framed by a bus.
+
+use super::motion::{Scroll, Trail};
+use super::source::{ByteSource, Gaps, Random, GAP_MIN};
+use super::{Ctx, Effect, Param};
+use crate::color::rgb;
+use crate::{Framebuffer, Rgb};
+
+const OFF: Rgb = rgb(0, 0, 0);
+pub(super) const BIT_ONE: Rgb = rgb(0, 200, 255);
+pub(super) const BIT_ZERO: Rgb = rgb(16, 0, 28);
+
+#[derive(Clone, Copy)]
+enum Src {
+    Bits { byte: u8, left: u8 },
+    Gap { left: u32 },
+}
+
+/// The cells scrolling down the wire, and the trail they leave: what
+/// [`Stream`] and [`Framed`](super::framing::Framed) share.
+pub(super) struct Lane {
+    scroll: Scroll,
+    trail: Option<Trail>,
+}
+
+impl Lane {
+    pub(super) fn new(len: usize) -> Self {
+        Self {
+            scroll: Scroll::new(len),
+            trail: None,
+        }
+    }
+
+    /// Leave a trail fading with time constant `tau_ms`.
+    pub(super) fn set_trail(&mut self, tau_ms: u32) {
+        self.trail = Some(Trail::new(tau_ms));
+    }
+
+    /// Scroll in as many cells from `next` as `ctx` moves the wire.
+    pub(super) fn tick(&mut self, ctx: &Ctx, mut next: impl FnMut() -> Rgb) {
+        for _ in 0..self.scroll.advance(ctx) {
+            self.scroll.push(next());
+        }
+        if let Some(trail) = &mut self.trail {
+            trail.update(ctx.velocity_q8);
+        }
+    }
+
+    pub(super) fn render(&self, fb: &mut Framebuffer) {
+        self.scroll.render(fb);
+        if let Some(trail) = &self.trail {
+            let n = fb.len().min(self.scroll.len());
+            trail.apply(&mut fb[..n]);
+        }
+    }
+}
+
+pub struct Stream<S = Random> {
+    lane: Lane,
+    source: S,
+    src: Src,
+    gaps: Gaps,
+}
+
+impl Stream {
+    /// A stream of random bytes for a strip of `len` LEDs (at most `MAX_LEDS`).
+    pub fn new(len: usize) -> Self {
+        Self::with_source(len, Random::default())
+    }
+}
+
+impl<S: ByteSource> Stream<S> {
+    /// A stream of the bytes from `source`.
+    pub fn with_source(len: usize, source: S) -> Self {
+        Self {
+            lane: Lane::new(len),
+            source,
+            src: Src::Gap { left: GAP_MIN },
+            gaps: Gaps::default(),
+        }
+    }
+
+    /// Leave a trail fading with time constant `tau_ms`.
+    pub fn with_trail(mut self, tau_ms: u32) -> Self {
+        self.lane.set_trail(tau_ms);
+        self
+    }
+}
+
+fn next_pixel<S: ByteSource>(src: &mut Src, source: &mut S, gaps: &Gaps) -> Rgb {
+    match *src {
+        Src::Bits { byte, left } => {
+            let bit = (byte >> (left - 1)) & 1;
+            let color = if bit == 1 { BIT_ONE } else { BIT_ZERO };
+            *src = if left > 1 {
+                Src::Bits {
+                    byte,
+                    left: left - 1,
+                }
+            } else {
+                Src::Gap {
+                    left: gaps.scale(source.gap()),
+                }
+            };
+            color
+        }
+        Src::Gap { left } => {
+            *src = if left > 1 {
+                Src::Gap { left: left - 1 }
+            } else {
+                match source.next_byte() {
+                    Some(byte) => Src::Bits { byte, left: 8 },
+                    None => Src::Gap { left: 1 }, // idle; ask again
+                }
+            };
+            OFF
+        }
+    }
+}
+
+impl<S: ByteSource> Effect for Stream<S> {
+    fn tick(&mut self, ctx: &Ctx) {
+        let (src, source, gaps) = (&mut self.src, &mut self.source, &self.gaps);
+        self.lane.tick(ctx, || next_pixel(src, source, gaps));
+    }
+
+    fn render(&self, fb: &mut Framebuffer) {
+        self.lane.render(fb);
+    }
+
+    fn params(&self) -> &'static [Param] {
+        Gaps::PARAMS
+    }
+
+    fn param(&self, index: usize) -> i32 {
+        self.gaps.param(index)
+    }
+
+    fn set_param(&mut self, index: usize, value: i32) {
+        self.gaps.set_param(index, value);
+    }
+}
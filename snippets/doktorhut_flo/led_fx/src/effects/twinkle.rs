@@ -0,0 +1,82 @@
+//! Random twinkles: LEDs light up in a random palette colour and fade out.
+
+use super::source::Random;
+use super::{Ctx, Effect};
+use crate::color::scale;
+use crate::palette::{Palette, Themed};
+use crate::{Framebuffer, MAX_LEDS};
+
+pub struct Twinkle {
+    palette: Themed,
+    rng: Random,
+    /// Twinkles started per second across the strip, and how long each fades.
+    per_second: u32,
+    fade_ms: u32,
+    spawn_ms: u32,
+    level: [u16; MAX_LEDS],
+    index: [u8; MAX_LEDS],
+}
+
+impl Twinkle {
+    pub fn new() -> Self {
+        Self {
+            palette: Themed::new(),
+            rng: Random::new(0x5EED_F00D),
+            per_second: 20,
+            fade_ms: 800,
+            spawn_ms: 0,
+            level: [0; MAX_LEDS],
+            index: [0; MAX_LEDS],
+        }
+    }
+
+    /// Always use `palette` instead of the theme.
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette.pin(palette);
+        self
+    }
+
+    /// Start `per_second` twinkles a second, each fading out over `fade_ms`.
+    pub fn with_rate(mut self, per_second: u32, fade_ms: u32) -> Self {
+        self.per_second = per_second;
+        self.fade_ms = fade_ms.max(1);
+        self
+    }
+}
+
+impl Default for Twinkle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Effect for Twinkle {
+    fn tick(&mut self, ctx: &Ctx) {
+        self.palette.resolve(ctx);
+        let len = ctx.len.min(MAX_LEDS);
+        let fade = (65535 * ctx.dt_ms / self.fade_ms).min(65535) as u16;
+        for l in self.level[..len].iter_mut() {
+            *l = l.saturating_sub(fade);
+        }
+        if len == 0 {
+            return;
+        }
+        // Spawn at the average rate; dt carries the remainder over.
+        self.spawn_ms += ctx.dt_ms * self.per_second;
+        while self.spawn_ms >= 1000 {
+            self.spawn_ms -= 1000;
+            let i = self.rng.range(0, len as u32 - 1) as usize;
+            self.level[i] = 65535;
+            self.index[i] = self.rng.next_u32() as u8;
+        }
+    }
+
+    fn render(&self, fb: &mut Framebuffer) {
+        for (i, px) in fb.iter_mut().enumerate() {
+            *px = match self.level.get(i) {
+                Some(&l) if l > 0 => scale(self.palette.at(self.index[i]), l as u32),
+                _ => [0; 3],
+            };
+        }
+    }
+}
@@ -0,0 +1,30 @@
+//! The platform-independent half of the LED strips: pixels and how matrices
+//! map them, colour math, effects and how they are layered, the music
+//! analysis and tempo clock behind the audio-reactive and beat-synced ones,
+//! and the supply current estimates of the power limiter.
+//! Nothing here touches hardware or reads `control` (effects get a
+//! [`effects::Ctx`] snapshot), so it builds for the host as well as the ESP32.
+
+#![no_std]
+
+pub mod audio;
+pub mod color;
+pub mod compose;
+pub mod effects;
+pub mod matrix;
+pub mod palette;
+pub mod playlist;
+pub mod power;
+pub mod tempo;
+pub mod transition;
+
+/// Longest supported strip. Each strip's length is set at runtime; this only
+/// sizes the per-strip framebuffer / effect storage and the driver's wire
+/// buffers (3-4 bytes/LED each).
+pub const MAX_LEDS: usize = 300;
+
+/// 16-bit RGB pixel (see [`color`]); the driver applies gamma/brightness and
+/// reorders to the chipset's colour order.
+pub type Rgb = [u16; 3];
+/// One strip's pixels, index 0 at the data-in end. Its length is the strip's.
+pub type Framebuffer = [Rgb];
@@ -0,0 +1,112 @@
+//! LED matrices. A panel is wired as one long strip (and driven as one), so
+//! its frame is an ordinary [`Framebuffer`]; an [`XyMap`] says which LED of it
+//! sits at each (x, y), and a [`Canvas`] lets 2D effects draw by coordinates.
+//! (0, 0) is the top-left corner as the panel is mounted.
+
+use crate::{Framebuffer, Rgb};
+
+/// Marks a position with no LED in a [`Layout::Lut`].
+pub const NO_LED: u16 = u16::MAX;
+
+/// How a panel's LEDs are wired through its rows.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Layout {
+    /// Every row runs left to right, data-in at the top left.
+    RowMajor,
+    /// Rows alternate direction (the usual zig-zag of WS2812 panels): the
+    /// first runs left to right, the second back, and so on.
+    Serpentine,
+    /// `lut[y * width + x]` is the LED at (x, y), or [`NO_LED`]: column-wise
+    /// wiring, tiled panels, odd shapes.
+    Lut(&'static [u16]),
+}
+
+/// A `width` x `height` panel wired as `layout`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct XyMap {
+    pub width: usize,
+    pub height: usize,
+    pub layout: Layout,
+}
+
+impl XyMap {
+    pub const fn new(width: usize, height: usize, layout: Layout) -> Self {
+        Self {
+            width,
+            height,
+            layout,
+        }
+    }
+
+    /// LEDs the panel's strip needs (past the highest one in a lookup table).
+    pub const fn len(&self) -> usize {
+        match self.layout {
+            Layout::RowMajor | Layout::Serpentine => self.width * self.height,
+            Layout::Lut(lut) => {
+                let mut n = 0;
+                let mut i = 0;
+                while i < lut.len() {
+                    if lut[i] != NO_LED && lut[i] as usize >= n {
+                        n = lut[i] as usize + 1;
+                    }
+                    i += 1;
+                }
+                n
+            }
+        }
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The LED at (x, y); None off the panel or in a gap.
+    pub fn index(&self, x: usize, y: usize) -> Option<usize> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        match self.layout {
+            Layout::RowMajor => Some(y * self.width + x),
+            Layout::Serpentine if y % 2 == 1 => Some(y * self.width + self.width - 1 - x),
+            Layout::Serpentine => Some(y * self.width + x),
+            Layout::Lut(lut) => match lut.get(y * self.width + x) {
+                Some(&i) if i != NO_LED => Some(i as usize),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// A frame seen through an [`XyMap`]. Positions off the panel, in a gap or
+/// past the end of the frame are ignored (read as black).
+pub struct Canvas<'a> {
+    map: XyMap,
+    fb: &'a mut Framebuffer,
+}
+
+impl<'a> Canvas<'a> {
+    pub fn new(map: XyMap, fb: &'a mut Framebuffer) -> Self {
+        Self { map, fb }
+    }
+
+    pub fn width(&self) -> usize {
+        self.map.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.map.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Rgb {
+        self.map
+            .index(x, y)
+            .and_then(|i| self.fb.get(i).copied())
+            .unwrap_or([0; 3])
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, c: Rgb) {
+        if let Some(px) = self.map.index(x, y).and_then(|i| self.fb.get_mut(i)) {
+            *px = c;
+        }
+    }
+}
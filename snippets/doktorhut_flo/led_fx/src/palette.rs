@@ -0,0 +1,291 @@
+//! Colour gradients. Effects pick colours by palette index (0..=255 from the
+//! first stop to the last) from the rig's current theme, [`Ctx::palette`], so
+//! the whole rig can be re-themed at runtime; [`PaletteFade`] blends from one
+//! theme to the next over time. The wire effects keep their fixed colours:
+//! there the colour is the data.
+//!
+//! [`Ctx::palette`]: crate::effects::Ctx::palette
+
+use core::ops::Deref;
+
+use crate::color::{hsv_to_rgb, rgb};
+use crate::effects::Ctx;
+use crate::transition::{Fade, Transition};
+use crate::Rgb;
+
+/// Number of stops in a [`Palette`].
+pub const STOPS: usize = 16;
+
+/// A gradient of [`STOPS`] evenly spaced colours; lookups interpolate between
+/// neighbouring stops in 16-bit.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Palette {
+    stops: [Rgb; STOPS],
+}
+
+impl Palette {
+    pub const fn new(stops: [Rgb; STOPS]) -> Self {
+        Self { stops }
+    }
+
+    /// One colour everywhere.
+    pub const fn solid(c: Rgb) -> Self {
+        Self { stops: [c; STOPS] }
+    }
+
+    /// Sample a gradient given as `(index, colour)` anchors in rising index
+    /// order, the first at 0 and the last at 255.
+    pub const fn gradient(anchors: &[(u8, Rgb)]) -> Self {
+        let mut stops = [[0; 3]; STOPS];
+        let mut i = 0;
+        while i < STOPS {
+            let index = (i * 255 / (STOPS - 1)) as u32;
+            // The anchors either side of `index`.
+            let mut k = 0;
+            while k + 2 < anchors.len() && anchors[k + 1].0 as u32 <= index {
+                k += 1;
+            }
+            let (lo, a) = anchors[k];
+            let (hi, b) = anchors[if k + 1 < anchors.len() { k + 1 } else { k }];
+            let t = match ((index - lo as u32) * 65535).checked_div(hi as u32 - lo as u32) {
+                Some(t) => t,
+                None => 0,
+            };
+            stops[i] = lerp(a, b, t);
+            i += 1;
+        }
+        Self { stops }
+    }
+
+    /// Colour at `index`: 0 is the first stop, 255 the last.
+    pub fn at(&self, index: u8) -> Rgb {
+        self.at_fine((index as u32 * 257) as u16)
+    }
+
+    /// Colour at a 16-bit `index` (0..=65535 from the first stop to the last),
+    /// for gradients smoother than 256 steps.
+    pub fn at_fine(&self, index: u16) -> Rgb {
+        // Q16 position between stops: 0..=(STOPS - 1) << 16.
+        let pos = index as u32 * (STOPS as u32 - 1);
+        let pos = pos + pos / 65535; // 65535 lands exactly on the last stop
+        let i = (pos >> 16) as usize;
+        let t = pos & 0xFFFF;
+        lerp(self.stops[i], self.stops[(i + 1).min(STOPS - 1)], t)
+    }
+
+    /// `self` moved `amount_q16` (0..=65535) of the way to `to`, stop by stop.
+    pub fn blend(&self, to: &Palette, amount_q16: u32) -> Palette {
+        let mut stops = self.stops;
+        for (s, &t) in stops.iter_mut().zip(to.stops.iter()) {
+            *s = lerp(*s, t, amount_q16);
+        }
+        Palette { stops }
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        RAINBOW
+    }
+}
+
+/// An effect's palette: the rig's theme, taken from [`Ctx::palette`] every
+/// tick, unless pinned to a fixed one.
+///
+/// [`Ctx::palette`]: crate::effects::Ctx::palette
+#[derive(Clone, Copy)]
+pub struct Themed {
+    pinned: Option<Palette>,
+    current: Palette,
+}
+
+impl Themed {
+    /// Following the theme.
+    pub const fn new() -> Self {
+        Self {
+            pinned: None,
+            current: RAINBOW,
+        }
+    }
+
+    /// Always use `palette` instead of the theme.
+    pub fn pin(&mut self, palette: Palette) {
+        self.pinned = Some(palette);
+        self.current = palette;
+    }
+
+    /// Pick up this tick's theme (a pinned palette stays).
+    pub fn resolve(&mut self, ctx: &Ctx) {
+        self.current = self.pinned.unwrap_or(ctx.palette);
+    }
+}
+
+impl Default for Themed {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for Themed {
+    type Target = Palette;
+
+    fn deref(&self) -> &Palette {
+        &self.current
+    }
+}
+
+/// Blends the current palette toward a new target over a transition's time.
+#[derive(Clone, Copy)]
+pub struct PaletteFade {
+    from: Palette,
+    to: Palette,
+    fade: Fade,
+}
+
+impl PaletteFade {
+    /// Showing `p`, no blend running.
+    pub fn new(p: Palette) -> Self {
+        Self {
+            from: p,
+            to: p,
+            fade: Fade::new(Transition::CUT),
+        }
+    }
+
+    /// Head for `to` over `transition` (a crossfade; the kind is ignored),
+    /// starting from whatever is showing now. Retargeting to the palette
+    /// already headed for does nothing.
+    pub fn set_target(&mut self, to: Palette, transition: Transition) {
+        if to != self.to {
+            self.from = self.current();
+            self.to = to;
+            self.fade = Fade::new(transition);
+        }
+    }
+
+    pub fn tick(&mut self, dt_ms: u32) {
+        self.fade.tick(dt_ms);
+    }
+
+    /// The palette showing now.
+    pub fn current(&self) -> Palette {
+        if self.fade.is_done() {
+            self.to
+        } else {
+            self.from.blend(&self.to, self.fade.progress_q16())
+        }
+    }
+}
+
+/// Per-channel `a + (b - a) * t`, `t_q16` 0..=65535.
+const fn lerp(a: Rgb, b: Rgb, t_q16: u32) -> Rgb {
+    let mut c = [0; 3];
+    let mut ch = 0;
+    while ch < 3 {
+        let (a, b) = (a[ch] as i64, b[ch] as i64);
+        c[ch] = (a + (b - a) * t_q16 as i64 / 65535) as u16;
+        ch += 1;
+    }
+    c
+}
+
+/// Full-saturation hue circle; its last stop is one step short of red, so
+/// cycling through the indices wraps smoothly.
+pub const RAINBOW: Palette = {
+    let mut stops = [[0; 3]; STOPS];
+    let mut i = 0;
+    while i < STOPS {
+        let (r, g, b) = hsv_to_rgb((i * 256 / STOPS) as u8, 255, 255);
+        stops[i] = rgb(r, g, b);
+        i += 1;
+    }
+    Palette::new(stops)
+};
+
+/// Black through red and yellow to white, for fire heat.
+pub const HEAT: Palette = Palette::new([
+    rgb(0, 0, 0),
+    rgb(51, 0, 0),
+    rgb(102, 0, 0),
+    rgb(153, 0, 0),
+    rgb(204, 0, 0),
+    rgb(255, 0, 0),
+    rgb(255, 51, 0),
+    rgb(255, 102, 0),
+    rgb(255, 153, 0),
+    rgb(255, 204, 0),
+    rgb(255, 255, 0),
+    rgb(255, 255, 51),
+    rgb(255, 255, 102),
+    rgb(255, 255, 153),
+    rgb(255, 255, 204),
+    rgb(255, 255, 255),
+]);
+
+/// Dark red glow through orange to bright yellow.
+pub const LAVA: Palette = Palette::gradient(&[
+    (0, rgb(18, 0, 0)),
+    (60, rgb(113, 0, 0)),
+    (120, rgb(255, 20, 0)),
+    (180, rgb(255, 110, 0)),
+    (230, rgb(255, 200, 20)),
+    (255, rgb(255, 255, 120)),
+]);
+
+/// Deep blue through teal to foam.
+pub const OCEAN: Palette = Palette::gradient(&[
+    (0, rgb(0, 0, 40)),
+    (70, rgb(0, 20, 140)),
+    (140, rgb(0, 120, 180)),
+    (200, rgb(0, 200, 170)),
+    (255, rgb(180, 255, 255)),
+]);
+
+/// Dark ale through amber to a white head.
+pub const BEER_AMBER: Palette = Palette::gradient(&[
+    (0, rgb(40, 10, 0)),
+    (90, rgb(200, 70, 0)),
+    (170, rgb(255, 150, 0)),
+    (225, rgb(255, 200, 40)),
+    (255, rgb(255, 250, 220)),
+]);
+
+/// The built-in themes, for menus.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Theme {
+    Rainbow,
+    Lava,
+    Ocean,
+    BeerAmber,
+    Heat,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 5] = [
+        Theme::Rainbow,
+        Theme::Lava,
+        Theme::Ocean,
+        Theme::BeerAmber,
+        Theme::Heat,
+    ];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Theme::Rainbow => "Rainbow",
+            Theme::Lava => "Lava",
+            Theme::Ocean => "Ocean",
+            Theme::BeerAmber => "Beer",
+            Theme::Heat => "Heat",
+        }
+    }
+
+    pub const fn palette(self) -> Palette {
+        match self {
+            Theme::Rainbow => RAINBOW,
+            Theme::Lava => LAVA,
+            Theme::Ocean => OCEAN,
+            Theme::BeerAmber => BEER_AMBER,
+            Theme::Heat => HEAT,
+        }
+    }
+}
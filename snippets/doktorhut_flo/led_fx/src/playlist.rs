@@ -0,0 +1,64 @@
+//! Timed, looping sequences of shows (e.g. what an idle strip plays). A
+//! playlist only keeps time; the caller maps its items to effects and
+//! crossfades into each with the entry's transition.
+
+use crate::transition::Transition;
+
+/// Show `item` for `ms`, entering it with `fade`.
+#[derive(Clone, Copy)]
+pub struct Entry<T> {
+    pub item: T,
+    pub ms: u32,
+    pub fade: Transition,
+}
+
+impl<T> Entry<T> {
+    pub const fn new(item: T, ms: u32, fade: Transition) -> Self {
+        Self { item, ms, fade }
+    }
+}
+
+/// Plays `entries` in order, over and over.
+pub struct Playlist<T: 'static> {
+    entries: &'static [Entry<T>],
+    index: usize,
+    elapsed_ms: u32,
+}
+
+impl<T: Copy> Playlist<T> {
+    pub const fn new(entries: &'static [Entry<T>]) -> Self {
+        Self {
+            entries,
+            index: 0,
+            elapsed_ms: 0,
+        }
+    }
+
+    /// The entry playing now (None for an empty playlist).
+    pub fn current(&self) -> Option<Entry<T>> {
+        self.entries.get(self.index).copied()
+    }
+
+    /// Advance by `dt_ms`, moving on to the next entry (or several, after a
+    /// long tick) once the current one has played its time.
+    pub fn tick(&mut self, dt_ms: u32) {
+        if self.entries.is_empty() {
+            return;
+        }
+        self.elapsed_ms += dt_ms;
+        loop {
+            let ms = self.entries[self.index].ms.max(1);
+            if self.elapsed_ms < ms {
+                break;
+            }
+            self.elapsed_ms -= ms;
+            self.index = (self.index + 1) % self.entries.len();
+        }
+    }
+
+    /// Back to the start of the first entry.
+    pub fn restart(&mut self) {
+        self.index = 0;
+        self.elapsed_ms = 0;
+    }
+}
@@ -0,0 +1,31 @@
+//! LED supply current estimates and the power limiter's scale factor. Only
+//! the lit part of a frame can be dimmed; the drivers' quiescent draw is there
+//! whatever the frame, so the scale shares out what the budget leaves of it.
+
+// ~20 mA per channel at full duty (60 mA per white WS2812B), plus ~1 mA
+// quiescent per LED for the driver IC, lit or not.
+const MA_PER_CHANNEL: u32 = 20;
+const IDLE_MA_PER_LED: u32 = 1;
+
+/// Quiescent current (mA) of `leds` dark LEDs.
+pub fn idle_ma(leds: usize) -> u32 {
+    leds as u32 * IDLE_MA_PER_LED
+}
+
+/// Estimated current (mA) of `leds` LEDs whose channel duties (0..=255 each)
+/// add up to `duty_sum`.
+pub fn estimate_ma(duty_sum: u32, leds: usize) -> u32 {
+    duty_sum * MA_PER_CHANNEL / 255 + idle_ma(leds)
+}
+
+/// Q16 factor (65536 = 1) to scale the duties of frames demanding `demand_ma`,
+/// `idle_ma` of it quiescent, by so they draw at most `budget_ma`. 0 if the
+/// idle current alone is over the budget.
+pub fn scale_q16(demand_ma: u32, idle_ma: u32, budget_ma: u32) -> u32 {
+    if demand_ma <= budget_ma {
+        return 65536;
+    }
+    let lit = demand_ma.saturating_sub(idle_ma).max(1);
+    let room = budget_ma.saturating_sub(idle_ma);
+    (room as u64 * 65536 / lit as u64) as u32
+}
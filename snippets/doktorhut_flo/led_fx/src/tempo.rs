@@ -0,0 +1,148 @@
+//! Tempo clock for beat-synced effects: a beat period and the time of a
+//! downbeat give the beat phase and bar position at any time. Set from a BPM
+//! or tapped in with [`TapTempo`].
+
+/// Beats per bar (4/4 time).
+pub const BEATS_PER_BAR: u32 = 4;
+/// Tempo range; taps and nudges are clamped to it.
+pub const BPM_MIN: u32 = 40;
+pub const BPM_MAX: u32 = 240;
+
+// Taps averaged into a tempo (the latest ones)...
+const TAPS: usize = 8;
+// ...and a pause longer than this starts a new run of taps.
+const TAP_RESET_MS: u32 = 2000;
+
+/// A running tempo: beats of `beat_ms` from the downbeat at `anchor_ms`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Tempo {
+    pub beat_ms: u32,
+    pub anchor_ms: u32,
+}
+
+/// Where a [`Tempo`] is at one moment (part of
+/// [`Ctx`](crate::effects::Ctx)).
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Beat {
+    /// Beat within the bar, 0 (the downbeat) to `BEATS_PER_BAR - 1`.
+    pub beat: u32,
+    /// Progress through the beat, 0..=65535.
+    pub phase_q16: u32,
+    pub beat_ms: u32,
+}
+
+fn beat_ms_for(bpm: u32) -> u32 {
+    60_000 / bpm.clamp(BPM_MIN, BPM_MAX)
+}
+
+impl Tempo {
+    /// `bpm` (clamped to the range) with a downbeat at `anchor_ms`.
+    pub fn from_bpm(bpm: u32, anchor_ms: u32) -> Self {
+        Self {
+            beat_ms: beat_ms_for(bpm),
+            anchor_ms,
+        }
+    }
+
+    /// Beats per minute, rounded.
+    pub fn bpm(&self) -> u32 {
+        (60_000 + self.beat_ms / 2) / self.beat_ms.max(1)
+    }
+
+    /// Position in the bar at `now_ms`, in beats (Q16).
+    fn bar_pos_q16(&self, now_ms: u32) -> u64 {
+        let bar_ms = (self.beat_ms * BEATS_PER_BAR).max(1);
+        let into_bar = now_ms.wrapping_sub(self.anchor_ms) % bar_ms;
+        ((into_bar as u64) << 16) / self.beat_ms.max(1) as u64
+    }
+
+    pub fn at(&self, now_ms: u32) -> Beat {
+        let pos = self.bar_pos_q16(now_ms);
+        Beat {
+            beat: (pos >> 16) as u32,
+            phase_q16: (pos & 0xFFFF) as u32,
+            beat_ms: self.beat_ms,
+        }
+    }
+
+    /// The same clock at `bpm`, still at the same point of the bar at
+    /// `now_ms` (so nudging the tempo does not jump the beat).
+    pub fn with_bpm(&self, bpm: u32, now_ms: u32) -> Self {
+        let beat_ms = beat_ms_for(bpm);
+        let elapsed = (self.bar_pos_q16(now_ms) * beat_ms as u64) >> 16;
+        Self {
+            beat_ms,
+            anchor_ms: now_ms.wrapping_sub(elapsed as u32),
+        }
+    }
+}
+
+impl Beat {
+    /// Progress through the bar, 0..=65535.
+    pub fn bar_phase_q16(&self) -> u32 {
+        (self.beat * 65536 + self.phase_q16) / BEATS_PER_BAR
+    }
+
+    pub fn is_downbeat(&self) -> bool {
+        self.beat == 0
+    }
+
+    /// A pulse for flashing on the beat: 65535 as it starts, falling
+    /// quadratically to 0 by the next.
+    pub fn pulse_q16(&self) -> u32 {
+        let rest = 65535 - self.phase_q16.min(65535);
+        rest * rest / 65535
+    }
+}
+
+/// Tap tempo: the average interval of the latest taps, the first tap of a
+/// run being a downbeat.
+pub struct TapTempo {
+    taps: [u32; TAPS],
+    n: usize,
+    /// Taps in this run (the bar position counts from its first).
+    count: u32,
+}
+
+impl TapTempo {
+    pub const fn new() -> Self {
+        Self {
+            taps: [0; TAPS],
+            n: 0,
+            count: 0,
+        }
+    }
+
+    /// Tap at `now_ms`; returns the tempo once there are two taps in a run.
+    pub fn tap(&mut self, now_ms: u32) -> Option<Tempo> {
+        if self.n > 0 && now_ms.wrapping_sub(self.taps[self.n - 1]) > TAP_RESET_MS {
+            self.n = 0;
+            self.count = 0;
+        }
+        if self.n == TAPS {
+            self.taps.copy_within(1.., 0);
+            self.n -= 1;
+        }
+        self.taps[self.n] = now_ms;
+        self.n += 1;
+        self.count += 1;
+        if self.n < 2 {
+            return None;
+        }
+        let span = now_ms.wrapping_sub(self.taps[0]);
+        let beat_ms =
+            (span / (self.n as u32 - 1)).clamp(beat_ms_for(BPM_MAX), beat_ms_for(BPM_MIN));
+        // In phase with the latest tap, counting bars from the run's first.
+        let beats_in = (self.count - 1) % BEATS_PER_BAR;
+        Some(Tempo {
+            beat_ms,
+            anchor_ms: now_ms.wrapping_sub(beats_in * beat_ms),
+        })
+    }
+}
+
+impl Default for TapTempo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
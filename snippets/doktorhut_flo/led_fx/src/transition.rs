@@ -0,0 +1,97 @@
+//! Transitions between what a layer shows: when a strip's effect changes, the
+//! outgoing one keeps running and is crossfaded or wiped into the incoming one
+//! (either side may be dark).
+
+use crate::compose::Mask;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Kind {
+    /// Incoming opacity rises while the outgoing effect stays underneath.
+    Crossfade,
+    /// The incoming effect sweeps in from the data-in end.
+    Wipe,
+}
+
+#[derive(Clone, Copy)]
+pub struct Transition {
+    pub kind: Kind,
+    /// 0 = hard cut.
+    pub duration_ms: u32,
+}
+
+impl Transition {
+    pub const CUT: Transition = Transition::crossfade(0);
+
+    pub const fn crossfade(duration_ms: u32) -> Self {
+        Self {
+            kind: Kind::Crossfade,
+            duration_ms,
+        }
+    }
+
+    pub const fn wipe(duration_ms: u32) -> Self {
+        Self {
+            kind: Kind::Wipe,
+            duration_ms,
+        }
+    }
+
+    pub const fn is_cut(&self) -> bool {
+        self.duration_ms == 0
+    }
+}
+
+/// A running transition.
+#[derive(Clone, Copy)]
+pub struct Fade {
+    transition: Transition,
+    elapsed_ms: u32,
+}
+
+impl Fade {
+    pub fn new(transition: Transition) -> Self {
+        Self {
+            transition,
+            elapsed_ms: 0,
+        }
+    }
+
+    pub fn tick(&mut self, dt_ms: u32) {
+        self.elapsed_ms = (self.elapsed_ms + dt_ms).min(self.transition.duration_ms);
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.elapsed_ms >= self.transition.duration_ms
+    }
+
+    /// 0..=65535 (start .. done).
+    pub fn progress_q16(&self) -> u32 {
+        match self.transition.duration_ms {
+            0 => 65535,
+            d => (self.elapsed_ms as u64 * 65535 / d as u64) as u32,
+        }
+    }
+
+    /// Opacity and mask of the incoming layer (drawn above the outgoing one).
+    pub fn incoming(&self) -> (u32, Mask) {
+        let p = self.progress_q16();
+        match self.transition.kind {
+            Kind::Crossfade => (p, Mask::All),
+            Kind::Wipe => (65535, Mask::Head(p)),
+        }
+    }
+
+    /// Opacity and mask of the outgoing layer. With an incoming layer on top
+    /// it stays whole (the incoming one covers it); fading to dark, it fades
+    /// or is wiped away itself.
+    pub fn outgoing(&self, covered: bool) -> (u32, Mask) {
+        if covered {
+            return (65535, Mask::All);
+        }
+        let p = self.progress_q16();
+        match self.transition.kind {
+            Kind::Crossfade => (65535 - p, Mask::All),
+            Kind::Wipe => (65535, Mask::Tail(p)),
+        }
+    }
+}
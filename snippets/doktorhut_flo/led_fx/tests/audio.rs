@@ -0,0 +1,140 @@
+//! Music analysis on synthetic recordings: each test renders a signal to WAV
+//! bytes at a typical file rate and runs them through the same path as a
+//! real recording (parse, resample, analyse).
+
+use std::f32::consts::TAU;
+
+use led_fx::audio::wav::{Wav, WavError};
+use led_fx::audio::{Analyser, Analysis, Resampler, BANDS, FFT_LEN, SAMPLE_RATE};
+
+const FILE_RATE: u32 = 44100;
+
+/// 16-bit PCM WAV bytes of `channels` interleaved channels.
+fn wav_bytes(samples: &[i16], channels: u16, rate: u32) -> Vec<u8> {
+    let data_len = samples.len() as u32 * 2;
+    let mut b = Vec::new();
+    b.extend_from_slice(b"RIFF");
+    b.extend_from_slice(&(36 + data_len).to_le_bytes());
+    b.extend_from_slice(b"WAVEfmt ");
+    b.extend_from_slice(&16u32.to_le_bytes());
+    b.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    b.extend_from_slice(&channels.to_le_bytes());
+    b.extend_from_slice(&rate.to_le_bytes());
+    b.extend_from_slice(&(rate * channels as u32 * 2).to_le_bytes());
+    b.extend_from_slice(&(channels * 2).to_le_bytes());
+    b.extend_from_slice(&16u16.to_le_bytes());
+    b.extend_from_slice(b"data");
+    b.extend_from_slice(&data_len.to_le_bytes());
+    for s in samples {
+        b.extend_from_slice(&s.to_le_bytes());
+    }
+    b
+}
+
+/// `secs` of mono audio at the file rate, `f(t)` in -1..=1.
+fn render(secs: f32, f: impl Fn(f32) -> f32) -> Vec<u8> {
+    let n = (secs * FILE_RATE as f32) as usize;
+    let samples: Vec<i16> = (0..n)
+        .map(|i| (f(i as f32 / FILE_RATE as f32).clamp(-1.0, 1.0) * 32767.0) as i16)
+        .collect();
+    wav_bytes(&samples, 1, FILE_RATE)
+}
+
+/// Every block's analysis of a WAV file.
+fn analyse(bytes: &[u8]) -> Vec<Analysis> {
+    let wav = Wav::parse(bytes).unwrap();
+    let mut resampler = Resampler::new(wav.sample_rate);
+    let mut analyser = Analyser::new();
+    let mut out = Vec::new();
+    for s in wav.samples() {
+        resampler.push(s, |s| out.extend(analyser.push(s)));
+    }
+    out
+}
+
+fn tone(freq: f32, amplitude: f32) -> impl Fn(f32) -> f32 {
+    move |t| amplitude * (TAU * freq * t).sin()
+}
+
+#[test]
+fn wav_mixes_channels_down() {
+    let stereo = wav_bytes(&[100, 300, -200, -400], 2, 22050);
+    let wav = Wav::parse(&stereo).unwrap();
+    assert_eq!((wav.sample_rate, wav.channels, wav.len()), (22050, 2, 2));
+    assert_eq!(wav.samples().collect::<Vec<_>>(), [200, -300]);
+
+    assert_eq!(Wav::parse(b"not a wav").err(), Some(WavError::NotWav));
+    let mut eight_bit = wav_bytes(&[0; 4], 1, 8000);
+    eight_bit[34] = 8; // bits per sample
+    assert_eq!(Wav::parse(&eight_bit).err(), Some(WavError::Unsupported));
+}
+
+#[test]
+fn resampling_keeps_the_duration() {
+    let mut r = Resampler::new(FILE_RATE);
+    let mut n = 0;
+    for _ in 0..FILE_RATE {
+        r.push(0, |_| n += 1);
+    }
+    assert!((SAMPLE_RATE - 1..=SAMPLE_RATE).contains(&n), "{n}");
+}
+
+#[test]
+fn a_tone_lights_its_band() {
+    // One tone well inside each of a few bands.
+    for (freq, band) in [(45.0, 0), (120.0, 2), (700.0, 5), (3000.0, 7)] {
+        let blocks = analyse(&render(1.0, tone(freq, 0.5)));
+        let last = blocks.last().unwrap();
+        let loudest = (0..BANDS).max_by_key(|&b| last.bands[b]).unwrap();
+        assert_eq!(loudest, band, "{freq} Hz: {:?}", last.bands);
+        assert!(last.bands[band] > 60000, "{freq} Hz: {:?}", last.bands);
+        // Two octaves away is far down.
+        for b in (0..BANDS).filter(|b| b.abs_diff(band) >= 3) {
+            assert!(last.bands[b] < 20000, "{freq} Hz: {:?}", last.bands);
+        }
+    }
+}
+
+#[test]
+fn silence_is_dark_and_beatless() {
+    // A little hiss, as from an idle mic.
+    let mut seed = 1u32;
+    let hiss = move |_| {
+        seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        (seed >> 16) as f32 / 65536.0 * 0.002 - 0.001
+    };
+    let hiss = std::cell::RefCell::new(hiss);
+    let blocks = analyse(&render(2.0, |t| (hiss.borrow_mut())(t)));
+    assert!(blocks
+        .iter()
+        .all(|a| !a.beat && a.level == 0 && a.bands == [0; BANDS]));
+}
+
+#[test]
+fn kicks_are_beats() {
+    // 120 BPM kick drum (a 55 Hz thump decaying over ~100 ms) over a steady
+    // hi-hat-ish tone.
+    let period = 0.5;
+    let kick = |t: f32| {
+        let since = t % period;
+        0.8 * (-since / 0.04).exp() * (TAU * 55.0 * since).sin()
+    };
+    let blocks = analyse(&render(8.0, |t| kick(t) + tone(3000.0, 0.05)(t)));
+    let beats: Vec<usize> = (0..blocks.len()).filter(|&i| blocks[i].beat).collect();
+    assert!((15..=17).contains(&beats.len()), "{} beats", beats.len());
+
+    // Each within a block or two of its kick.
+    let block_s = FFT_LEN as f32 / SAMPLE_RATE as f32;
+    for &i in &beats {
+        let t = i as f32 * block_s;
+        let after_kick = t % period;
+        assert!(
+            after_kick < 2.0 * block_s || period - after_kick < block_s,
+            "beat at {t:.3}s"
+        );
+    }
+
+    // A steady tone is no beat (after its onset).
+    let blocks = analyse(&render(3.0, tone(60.0, 0.5)));
+    assert!(blocks.iter().filter(|a| a.beat).count() <= 1);
+}
@@ -0,0 +1,50 @@
+//! Wire encoding: dithering averages out to the undithered level.
+
+use led_fx::color::{brightness_q16, duty, encode_channel, encode_channel_dithered};
+
+/// Mean output of `frames` dithered frames of the constant value `v`.
+fn dithered_mean(v: u16, bright_q16: u32, frames: u32) -> f32 {
+    let mut err = 0;
+    let sum: u32 = (0..frames)
+        .map(|_| encode_channel_dithered(v, bright_q16, &mut err) as u32)
+        .sum();
+    sum as f32 / frames as f32
+}
+
+#[test]
+fn dithered_mid_level_averages_to_the_input() {
+    for (v, level) in [(32768, 8), (20000, 3), (9000, 1), (50000, 5)] {
+        let bright = brightness_q16(level);
+        let want = duty(v, bright) as f32 / 257.0;
+        let mean = dithered_mean(v, bright, 256);
+        assert!(
+            (mean - want).abs() < 0.02,
+            "{v} at level {level}: mean {mean}, want {want}"
+        );
+    }
+}
+
+#[test]
+fn dither_keeps_dim_values_alive() {
+    // Below half an output step the plain encoding rounds to dark.
+    let (v, bright) = (12000, brightness_q16(1));
+    assert_eq!(encode_channel(v, bright), 0);
+    assert!(dithered_mean(v, bright, 256) > 0.0);
+}
+
+#[test]
+fn limit_scaled_brightness_averages_to_the_limited_level() {
+    // The power limit scales the brightness before dithering, so the mean is
+    // the limited level with no error carried over from the unlimited one.
+    let (v, bright) = (40000, brightness_q16(6));
+    let limited = ((bright as u64 * 40000) >> 16) as u32;
+    let mut err = 0;
+    for _ in 0..10 {
+        encode_channel_dithered(v, bright, &mut err);
+    }
+    let sum: u32 = (0..256)
+        .map(|_| encode_channel_dithered(v, limited, &mut err) as u32)
+        .sum();
+    let want = duty(v, limited) as f32 / 257.0;
+    assert!((sum as f32 / 256.0 - want).abs() < 0.02);
+}
@@ -0,0 +1,135 @@
+//! Blend modes and opacity at the extremes of the 16-bit range, and layers
+//! drawn onto a frame.
+
+use led_fx::compose::{blend_px, Blend, Compositor, Layer, Mask};
+use led_fx::effects::{Compose, Ctx, Effect};
+use led_fx::{Framebuffer, Rgb, MAX_LEDS};
+
+const FULL: u32 = 65535;
+
+#[test]
+fn blend_modes() {
+    let d = [65535, 32768, 0];
+    let s = [32768, 65535, 65535];
+    assert_eq!(blend_px(d, s, Blend::Replace, FULL), s);
+    assert_eq!(blend_px(d, s, Blend::Add, FULL), [65535, 65535, 65535]);
+    assert_eq!(blend_px(d, s, Blend::Multiply, FULL), [32768, 32768, 0]);
+    assert_eq!(blend_px(d, s, Blend::Screen, FULL), [65535, 65535, 65535]);
+    assert_eq!(blend_px(d, s, Blend::Max, FULL), [65535, 65535, 65535]);
+}
+
+#[test]
+fn opacity_mixes_with_what_is_below() {
+    let (black, white) = ([0; 3], [65535; 3]);
+    assert_eq!(blend_px(black, white, Blend::Replace, 0), black);
+    assert_eq!(blend_px(black, white, Blend::Replace, FULL), white);
+    assert_eq!(blend_px(white, black, Blend::Replace, FULL), black);
+    assert_eq!(blend_px(black, white, Blend::Replace, 32768), [32768; 3]);
+}
+
+#[test]
+fn full_opacity_full_step_does_not_overflow() {
+    // 0xFFFF * 65535 is past i32::MAX: the mix has to be done wider.
+    let (black, white) = ([0; 3], [0xFFFF; 3]);
+    for blend in [Blend::Replace, Blend::Add, Blend::Screen, Blend::Max] {
+        assert_eq!(blend_px(black, white, blend, FULL), white);
+    }
+    assert_eq!(blend_px(white, black, Blend::Multiply, FULL), black);
+    assert_eq!(
+        blend_px([0xFFFF, 0, 0], [0, 0xFFFF, 0], Blend::Replace, FULL),
+        [0, 0xFFFF, 0]
+    );
+}
+
+/// Paints `pixels` (repeated along the strip), as an overlay or not.
+struct Paint {
+    pixels: &'static [Rgb],
+    overlay: bool,
+    ticks: u32,
+}
+
+impl Paint {
+    fn new(pixels: &'static [Rgb], overlay: bool) -> Self {
+        Self {
+            pixels,
+            overlay,
+            ticks: 0,
+        }
+    }
+}
+
+impl Effect for Paint {
+    fn tick(&mut self, _ctx: &Ctx) {
+        self.ticks += 1;
+    }
+
+    fn render(&self, fb: &mut Framebuffer) {
+        for (px, &c) in fb.iter_mut().zip(self.pixels.iter().cycle()) {
+            *px = c;
+        }
+    }
+
+    fn compose(&self) -> Compose {
+        if self.overlay {
+            Compose::Overlay
+        } else {
+            Compose::Replace
+        }
+    }
+}
+
+const RED: Rgb = [65535, 0, 0];
+const BLUE: Rgb = [0, 0, 65535];
+
+/// Draw `layer` onto an 8-LED frame of `below`.
+fn draw(below: Rgb, layer: Layer) -> Vec<Rgb> {
+    let mut scratch = Box::new([[0; 3]; MAX_LEDS]);
+    let mut comp = Compositor::with_scratch(&mut scratch);
+    let mut fb = vec![below; 8];
+    let ctx = Ctx {
+        len: fb.len(),
+        ..Ctx::default()
+    };
+    comp.draw(layer, &ctx, &mut fb);
+    fb
+}
+
+#[test]
+fn overlay_leaves_its_black_pixels_to_the_layers_below() {
+    let mut dots = Paint::new(&[RED, [0; 3]], true);
+    let fb = draw(BLUE, Layer::new(&mut dots));
+    assert_eq!(fb, [RED, BLUE].repeat(4));
+    // The same pixels as a plain layer paint the black ones too.
+    let mut dots = Paint::new(&[RED, [0; 3]], false);
+    let fb = draw(BLUE, Layer::new(&mut dots));
+    assert_eq!(fb, [RED, [0; 3]].repeat(4));
+}
+
+#[test]
+fn mask_restricts_the_blend() {
+    let mut red = Paint::new(&[RED], false);
+    let fb = draw(BLUE, Layer::new(&mut red).with_mask(Mask::Head(16384)));
+    assert_eq!(fb, [&[RED; 2][..], &[BLUE; 6]].concat());
+    let fb = draw(BLUE, Layer::new(&mut red).with_mask(Mask::Tail(16384)));
+    assert_eq!(fb, [&[BLUE; 2][..], &[RED; 6]].concat());
+    // Opacity only applies inside the mask.
+    let layer = Layer::new(&mut red)
+        .with_mask(Mask::Head(32768))
+        .with_opacity_q16(32768);
+    let fb = draw([0; 3], layer);
+    assert_eq!(fb, [[[32768, 0, 0]; 4], [[0; 3]; 4]].concat());
+}
+
+#[test]
+fn transparent_layer_is_ticked_but_not_drawn() {
+    let mut red = Paint::new(&[RED], false);
+    let fb = draw(BLUE, Layer::new(&mut red).with_opacity_q16(0));
+    assert_eq!(fb, [BLUE; 8]);
+    assert_eq!(red.ticks, 1, "the animation keeps running underneath");
+}
+
+#[test]
+fn there_is_one_static_compositor() {
+    assert!(Compositor::new().is_some());
+    assert!(Compositor::new().is_none());
+}
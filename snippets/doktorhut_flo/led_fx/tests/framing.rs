@@ -0,0 +1,140 @@
+//! Bus framings: every frame element in its place and colour, bits in
+//! transmission order.
+
+use led_fx::color::rgb;
+use led_fx::effects::framing::{I2c, Spi, Uart};
+use led_fx::effects::source::ByteTap;
+use led_fx::effects::{ByteSource, Ctx, Effect, Framed, REF_FRAME_MS};
+use led_fx::Rgb;
+
+// Long enough for a whole MPU6050 burst read.
+const LEN: usize = 200;
+const OFF: Rgb = [0; 3];
+const ONE: Rgb = rgb(0, 200, 255);
+const ZERO: Rgb = rgb(16, 0, 28);
+
+/// The given bytes once, then idle.
+struct Bytes(Vec<u8>);
+
+impl ByteSource for Bytes {
+    fn next_byte(&mut self) -> Option<u8> {
+        (!self.0.is_empty()).then(|| self.0.remove(0))
+    }
+}
+
+fn bits_msb_first(byte: u8) -> Vec<Rgb> {
+    (0..8)
+        .rev()
+        .map(|k| if byte >> k & 1 == 1 { ONE } else { ZERO })
+        .collect()
+}
+
+/// The first `n` cells sent, in order, at one LED per frame.
+fn sent(effect: &mut dyn Effect, n: usize) -> Vec<Rgb> {
+    let ctx = Ctx {
+        dt_ms: REF_FRAME_MS,
+        len: LEN,
+        velocity_q8: 256,
+        ..Ctx::default()
+    };
+    for _ in 0..n {
+        effect.tick(&ctx);
+    }
+    let mut fb = vec![OFF; LEN];
+    effect.render(&mut fb);
+    fb[..n].iter().rev().copied().collect()
+}
+
+#[test]
+fn uart_is_8n1_lsb_first() {
+    let mut uart = Framed::with_source(LEN, Uart, Bytes(vec![0b0000_0011]));
+    let start = rgb(0, 255, 0);
+    let stop = rgb(255, 0, 0);
+    let mut want = vec![start, ONE, ONE];
+    want.extend([ZERO; 6]);
+    // Stop bit, the gap, then idle.
+    want.extend([stop, OFF, OFF, OFF, OFF]);
+    assert_eq!(sent(&mut uart, want.len()), want);
+}
+
+const I2C_START: Rgb = rgb(255, 255, 255);
+const I2C_STOP: Rgb = rgb(255, 0, 0);
+const I2C_READ: Rgb = rgb(255, 0, 160);
+const I2C_WRITE: Rgb = rgb(40, 0, 24);
+const ACK: Rgb = rgb(0, 255, 0);
+const NACK: Rgb = rgb(0, 40, 0);
+
+/// (Repeated) START, `address` as 7 bits, R/W and the device's ACK.
+fn i2c_address(address: u8, read: bool) -> Vec<Rgb> {
+    let (one, zero) = (rgb(255, 150, 0), rgb(40, 20, 0));
+    let mut cells = vec![I2C_START];
+    cells.extend(
+        (0..7)
+            .rev()
+            .map(|k| if address >> k & 1 == 1 { one } else { zero }),
+    );
+    cells.extend([if read { I2C_READ } else { I2C_WRITE }, ACK]);
+    cells
+}
+
+#[test]
+fn i2c_frames_address_and_acked_bytes() {
+    // Unmarked bytes: the first is the address byte (0x68, write).
+    let mut want = i2c_address(0x68, false);
+    for byte in [0xA5, 0x3C] {
+        want.extend(bits_msb_first(byte));
+        want.push(ACK);
+    }
+    want.extend([I2C_STOP, OFF, OFF]);
+    // Two bytes a transaction: the next byte opens another.
+    want.push(I2C_START);
+
+    let bytes = Bytes(vec![0xD0, 0xA5, 0x3C, 0xD1]);
+    let mut i2c = Framed::with_source(LEN, I2c::new(), bytes);
+    assert_eq!(sent(&mut i2c, want.len()), want);
+}
+
+#[test]
+fn i2c_closes_early_when_the_source_runs_dry() {
+    let mut i2c = Framed::with_source(LEN, I2c::new(), Bytes(vec![0xFE, 0x00]));
+    let got = sent(&mut i2c, 1 + 7 + 2 + 9 + 1);
+    assert_eq!(got[got.len() - 1], I2C_STOP, "STOP after one byte");
+}
+
+#[test]
+fn i2c_frames_tapped_mpu6050_burst_read() {
+    // What the tapped IMU pushes for a sensor read: write the register
+    // address 0x3B, repeated START, read 14 bytes, STOP.
+    static TAP: ByteTap<32> = ByteTap::new();
+    let reader = TAP.reader();
+    let data: Vec<u8> = (0..14).map(|i| i * 17).collect();
+    TAP.push_i2c(0x68, [(false, &[0x3B][..]), (true, &data[..])]);
+
+    let mut want = i2c_address(0x68, false);
+    want.extend(bits_msb_first(0x3B));
+    want.push(ACK);
+    want.extend(i2c_address(0x68, true));
+    for (i, &byte) in data.iter().enumerate() {
+        want.extend(bits_msb_first(byte));
+        want.push(if i == 13 { NACK } else { ACK });
+    }
+    want.extend([I2C_STOP, OFF, OFF]);
+
+    let mut i2c = Framed::with_source(LEN, I2c::new(), reader);
+    assert_eq!(sent(&mut i2c, want.len()), want);
+}
+
+#[test]
+fn spi_clocks_every_bit_inside_chip_select() {
+    let (cs, clk) = (rgb(255, 200, 0), rgb(24, 24, 24));
+    let mut want = vec![cs];
+    for byte in [0x81, 0x7E] {
+        for bit in bits_msb_first(byte) {
+            want.extend([clk, bit]);
+        }
+    }
+    want.extend([cs, OFF, OFF]);
+
+    let mut spi = Framed::with_source(LEN, Spi, Bytes(vec![0x81, 0x7E]));
+    assert_eq!(sent(&mut spi, want.len()), want);
+}
@@ -0,0 +1,510 @@
+//! Golden-frame regression tests. Each effect runs for a fixed number of frames
+//! with fixed inputs and its framebuffers must match the sequence recorded in
+//! `tests/golden/<name>.txt` (one line per frame, `.` = off, else the pixel as
+//! `rrrrggggbbbb` hex). After an intended visual change, re-record with
+//! `UPDATE_GOLDEN=1 cargo test` and review the diff.
+
+use std::fmt::Write;
+use std::{env, fs, path::PathBuf};
+
+use led_fx::audio::Audio;
+use led_fx::effects::spectrum::MODE_SPECTRUM;
+use led_fx::effects::{
+    BeerByte, Chase, Comet, Ctx, Effect, Fire, Larson, Packet, Party, Plasma, Spectrum, Stream,
+    Twinkle, REF_FRAME_MS,
+};
+use led_fx::palette::{Palette, HEAT};
+use led_fx::Rgb;
+
+const BEER_BYTE: u8 = 0b1011_0010;
+const WHITE: Rgb = [65535; 3];
+
+fn ctx(frame: u32, len: usize, velocity_q8: u32) -> Ctx {
+    Ctx {
+        now_ms: frame * REF_FRAME_MS,
+        dt_ms: REF_FRAME_MS,
+        len,
+        velocity_q8,
+        accel: [0.0, 0.0, 1.0],
+        ..Ctx::default()
+    }
+}
+
+/// Enter `effect`, then tick + render it once per context.
+fn run(effect: &mut dyn Effect, ctxs: impl IntoIterator<Item = Ctx>) -> Vec<Vec<Rgb>> {
+    let mut frames = Vec::new();
+    for (i, c) in ctxs.into_iter().enumerate() {
+        if i == 0 {
+            effect.on_enter(&c);
+        }
+        effect.tick(&c);
+        let mut fb = vec![[0; 3]; c.len];
+        effect.render(&mut fb);
+        frames.push(fb);
+    }
+    frames
+}
+
+fn format(frames: &[Vec<Rgb>]) -> String {
+    let mut s = String::new();
+    for fb in frames {
+        let px: Vec<String> = fb
+            .iter()
+            .map(|&[r, g, b]| match r | g | b {
+                0 => ".".into(),
+                _ => format!("{r:04x}{g:04x}{b:04x}"),
+            })
+            .collect();
+        writeln!(s, "{}", px.join(" ")).unwrap();
+    }
+    s
+}
+
+fn check_golden(name: &str, frames: &[Vec<Rgb>]) {
+    let path: PathBuf = [
+        env!("CARGO_MANIFEST_DIR"),
+        "tests",
+        "golden",
+        &format!("{name}.txt"),
+    ]
+    .iter()
+    .collect();
+    let got = format(frames);
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&path, &got).unwrap();
+        return;
+    }
+    let want = fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("{}: {e} (record with UPDATE_GOLDEN=1)", path.display()));
+    for (i, (g, w)) in got.lines().zip(want.lines()).enumerate() {
+        assert_eq!(g, w, "{name}: frame {i} differs from {}", path.display());
+    }
+    assert_eq!(
+        got.lines().count(),
+        want.lines().count(),
+        "{name}: frame count"
+    );
+}
+
+#[test]
+fn stream_golden() {
+    // 1 LED per frame: every frame scrolls in exactly one new cell.
+    let frames = run(&mut Stream::new(24), (0..64).map(|f| ctx(f, 24, 256)));
+    check_golden("stream", &frames);
+}
+
+#[test]
+fn slow_stream_with_trail_golden() {
+    // ~0.1 LED per frame: cells glide across LED boundaries.
+    let mut stream = Stream::new(16).with_trail(40);
+    check_golden(
+        "stream_slow_trail",
+        &run(&mut stream, (0..80).map(|f| ctx(f, 16, 26))),
+    );
+}
+
+#[test]
+fn stream_is_deterministic() {
+    let a = run(&mut Stream::new(24), (0..200).map(|f| ctx(f, 24, 300)));
+    let b = run(&mut Stream::new(24), (0..200).map(|f| ctx(f, 24, 300)));
+    assert!(a == b, "two streams from the same seed diverged");
+}
+
+#[test]
+fn packet_golden() {
+    let frames = run(
+        &mut Packet::new(BEER_BYTE),
+        (0..40).map(|f| ctx(f, 16, 192)),
+    );
+    check_golden("packet", &frames);
+}
+
+#[test]
+fn packet_wraps_around() {
+    // At 1 LED/frame the byte enters 8 LEDs before the strip and wraps once
+    // its head runs off the end: a period of len + 8 frames.
+    let len = 16;
+    let frames = run(
+        &mut Packet::new(BEER_BYTE),
+        (0..100).map(|f| ctx(f, len, 256)),
+    );
+    let period = len + 8;
+    for (i, fb) in frames.iter().enumerate().skip(period) {
+        assert!(
+            *fb == frames[i - period],
+            "frame {i} != frame {}",
+            i - period
+        );
+    }
+    // Not trivially periodic: it moves, and the strip is dark when it wraps.
+    assert!(frames[0] != frames[1]);
+    assert!(frames.iter().any(|fb| fb.iter().all(|&px| px == [0; 3])));
+}
+
+#[test]
+fn wide_packet_wraps_around() {
+    let (len, width) = (20, 3);
+    let mut packet = Packet::new(BEER_BYTE).with_bit_width(width);
+    let frames = run(&mut packet, (0..120).map(|f| ctx(f, len, 256)));
+    let period = len + 8 * width as usize;
+    for (i, fb) in frames.iter().enumerate().skip(period) {
+        assert!(
+            *fb == frames[i - period],
+            "frame {i} != frame {}",
+            i - period
+        );
+    }
+}
+
+#[test]
+fn packet_moves_smoothly() {
+    // Half way between LEDs, each bit lights two LEDs at half its colour.
+    let mut packet = Packet::new(0xFF);
+    packet.on_enter(&ctx(0, 16, 0));
+    for f in 1..=(8 * 2 + 4) {
+        packet.tick(&ctx(f, 16, 128)); // 0.5 LED per frame
+    }
+    // 20 half-steps from -8: head at 2.0; one more -> 2.5.
+    packet.tick(&ctx(21, 16, 128));
+    let mut fb = vec![[0; 3]; 16];
+    packet.render(&mut fb);
+    let full = fb[5];
+    assert_eq!(fb[2], fb[10], "split ends");
+    assert!(
+        fb[2][2] > 0 && fb[2][2] < full[2],
+        "{:?} vs {:?}",
+        fb[2],
+        full
+    );
+    assert_eq!(fb[1], [0; 3]);
+    assert_eq!(fb[11], [0; 3]);
+}
+
+#[test]
+fn trail_keeps_zero_bits_dark_at_manual_speed() {
+    // BEER MANUAL: 4 LEDs per frame, 40 ms trail. After 8 frames the tail is
+    // at LED 24 (the shot starts 8 LEDs before the strip).
+    let mut beer = BeerByte::new(BEER_BYTE).with_trail(40);
+    let fb = run(&mut beer, (0..8).map(|f| ctx(f, 60, 1024)))
+        .pop()
+        .unwrap();
+    let tail = 24;
+    let (one, zero) = (fb[tail], fb[tail + 1]);
+    assert!(
+        one[0] > 4 * zero[0] && zero != [0; 3],
+        "{one:?} vs {zero:?}"
+    );
+    for k in 0..8 {
+        let want = if BEER_BYTE >> (7 - k) & 1 == 1 {
+            one
+        } else {
+            zero
+        };
+        assert_eq!(fb[tail + k], want, "bit {k}");
+    }
+    // The trail is behind the tail, fading away from it; nothing ahead.
+    assert!(fb[tail - 1] != [0; 3] && fb[tail - 1][0] < one[0]);
+    assert!(fb[tail - 10][0] < fb[tail - 1][0]);
+    assert!(fb[tail + 8..].iter().all(|&px| px == [0; 3]));
+}
+
+#[test]
+fn beer_golden() {
+    let frames = run(
+        &mut BeerByte::new(BEER_BYTE),
+        (0..24).map(|f| ctx(f, 12, 160)),
+    );
+    check_golden("beer", &frames);
+}
+
+#[test]
+fn beer_finishes_when_it_leaves_the_strip() {
+    // Starts its width before the strip (8 LEDs a byte, 1 between bytes),
+    // finishes once its tail passes the end: (len + width) LEDs at `velocity`
+    // Q8.8 LEDs per frame.
+    let packet = [0xFF, 0xFF, 0x01, 0x05, 0x03, 0x2A, 0xD0, 0x07, 0xF5];
+    for (bytes, width) in [(&[BEER_BYTE][..], 8), (&packet[..], 9 * 9 - 1)] {
+        for len in [1, 12, 60, 300] {
+            for velocity_q8 in [1, 64, 256, 300, 1024] {
+                let mut beer = BeerByte::new(0);
+                beer.set_bytes(bytes);
+                beer.on_enter(&ctx(0, len, velocity_q8));
+                let expect = ((len as u32 + width) * 256).div_ceil(velocity_q8);
+                let mut frames = 0;
+                while !beer.is_finished() {
+                    frames += 1;
+                    beer.tick(&ctx(frames, len, velocity_q8));
+                    assert!(
+                        frames <= expect,
+                        "len {len} vel {velocity_q8}: still running"
+                    );
+                }
+                assert_eq!(frames, expect, "len {len} vel {velocity_q8}");
+
+                // Finished: draws nothing, and stays finished.
+                beer.tick(&ctx(frames + 1, len, velocity_q8));
+                let mut fb = vec![[0; 3]; len];
+                beer.render(&mut fb);
+                assert!(beer.is_finished() && fb.iter().all(|&px| px == [0; 3]));
+            }
+        }
+    }
+}
+
+#[test]
+fn beer_packet_goes_out_as_on_the_wire() {
+    // Three bytes, 26 cells; after 31 frames at 1 LED/frame the tail is at 5.
+    let mut beer = BeerByte::new(0);
+    beer.set_bytes(&[0xFF, 0x00, 0x0F]);
+    let fb = run(&mut beer, (0..31).map(|f| ctx(f, 40, 256)))
+        .pop()
+        .unwrap();
+    let (one, zero) = (fb[30], fb[14]);
+    assert!(one != zero && one != [0; 3] && zero != [0; 3]);
+
+    // First byte leads; each byte LSB first, so its MSB trails (left).
+    let mut want = vec![[0; 3]; 40];
+    for (j, byte) in [0xFFu8, 0x00, 0x0F].into_iter().enumerate() {
+        let start = 5 + (2 - j) * 9;
+        for k in 0..8 {
+            want[start + k] = if byte >> (7 - k) & 1 == 1 { one } else { zero };
+        }
+    }
+    assert_eq!(fb, want);
+}
+
+#[test]
+fn beer_refires_on_enter() {
+    let mut beer = BeerByte::new(BEER_BYTE);
+    assert!(beer.is_finished(), "idle until fired");
+    beer.on_enter(&ctx(0, 12, 256));
+    assert!(!beer.is_finished());
+}
+
+/// Centre of the white ball.
+fn ball_centre(fb: &[Rgb]) -> i32 {
+    let lit: Vec<i32> = (0..fb.len() as i32)
+        .filter(|&i| fb[i as usize] == WHITE)
+        .collect();
+    assert!(!lit.is_empty(), "no ball");
+    (lit[0] + lit[lit.len() - 1]) / 2
+}
+
+#[test]
+fn party_ball_follows_roll() {
+    let len = 61;
+    for roll in [-90, -60, -30, 0, 30, 60, 90] {
+        let mut party = Party::new(0, true);
+        party.tick(&Ctx {
+            roll,
+            ..ctx(0, len, 256)
+        });
+        let mut fb = vec![[0; 3]; len];
+        party.render(&mut fb);
+        let want = (roll + 90) * (len as i32 - 1) / 180;
+        // The ball is clipped at the ends; compare where it is whole.
+        if (4..len as i32 - 4).contains(&want) {
+            assert_eq!(ball_centre(&fb), want, "roll {roll}");
+        } else {
+            assert_eq!(fb[want as usize], WHITE, "roll {roll}");
+        }
+    }
+}
+
+#[test]
+fn party_roll_is_clamped() {
+    let len = 30;
+    let render = |roll| {
+        let mut party = Party::new(0, true);
+        party.tick(&Ctx {
+            roll,
+            ..ctx(0, len, 256)
+        });
+        let mut fb = vec![[0; 3]; len];
+        party.render(&mut fb);
+        fb
+    };
+    assert!(render(-180) == render(-90));
+    assert!(render(170) == render(90));
+}
+
+#[test]
+fn party_golden() {
+    // Tilt sweep, then a shake.
+    let ctxs = (0..48).map(|f| Ctx {
+        roll: -90 + f as i32 * 4,
+        accel: if f >= 40 {
+            [0.0, 0.0, 1.5]
+        } else {
+            [0.0, 0.0, 1.0]
+        },
+        ..ctx(f, 20, 256)
+    });
+    check_golden("party_ball", &run(&mut Party::new(0, true), ctxs.clone()));
+    check_golden("party_level", &run(&mut Party::new(85, false), ctxs));
+}
+
+#[test]
+fn library_golden() {
+    let ctxs = || (0..32).map(|f| ctx(f, 16, 160));
+    check_golden("comet", &run(&mut Comet::new(), ctxs()));
+    let twinkle = &mut Twinkle::new().with_rate(200, 100);
+    check_golden("twinkle", &run(twinkle, ctxs()));
+    check_golden("fire", &run(&mut Fire::new().with_palette(HEAT), ctxs()));
+    check_golden("larson", &run(&mut Larson::new(), ctxs()));
+    check_golden("chase", &run(&mut Chase::new(), ctxs()));
+    let plasma = &mut Plasma::new().with_wave(8, 200);
+    check_golden("plasma", &run(plasma, ctxs()));
+}
+
+#[test]
+fn larson_bounces_between_the_ends() {
+    let red = Palette::solid([65535, 0, 0]);
+    let mut larson = Larson::new().with_palette(red).with_eye(3, 0);
+    let len = 10;
+    let mut seen = vec![false; len];
+    for f in 0..200 {
+        let frame = run_once(&mut larson, ctx(f, len, 256));
+        // Exactly the 3-LED eye, inside the strip.
+        let lit: Vec<usize> = (0..len).filter(|&i| frame[i] != [0; 3]).collect();
+        assert_eq!(lit.len(), 3, "frame {f}: {frame:?}");
+        assert_eq!(lit[2] - lit[0], 2);
+        lit.iter().for_each(|&i| seen[i] = true);
+    }
+    assert!(seen.iter().all(|&s| s), "reaches both ends");
+}
+
+#[test]
+fn chase_lights_every_nth_led() {
+    let mut chase = Chase::new().with_spacing(4);
+    let mut offsets = Vec::new();
+    for f in 0..8 {
+        let frame = run_once(&mut chase, ctx(f, 20, 256));
+        let lit: Vec<usize> = (0..20).filter(|&i| frame[i] != [0; 3]).collect();
+        assert_eq!(lit.len(), 5);
+        assert!(lit.windows(2).all(|w| w[1] - w[0] == 4));
+        offsets.push(lit[0]);
+    }
+    // One LED on per frame at 1 LED/frame, wrapping at the spacing.
+    assert_eq!(offsets, [1, 2, 3, 0, 1, 2, 3, 0]);
+}
+
+#[test]
+fn fire_burns_from_the_base() {
+    let mut fire = Fire::new().with_palette(HEAT);
+    let len = 60;
+    fire.on_enter(&ctx(0, len, 256));
+    let mut total = vec![0u64; len];
+    for f in 0..2000 {
+        let frame = run_once(&mut fire, ctx(f, len, 256));
+        for (t, px) in total.iter_mut().zip(&frame) {
+            *t += px.iter().map(|&c| c as u64).sum::<u64>();
+        }
+    }
+    let base: u64 = total[..10].iter().sum();
+    let top: u64 = total[len - 10..].iter().sum();
+    assert!(base > 0 && base > 4 * top, "base {base}, top {top}");
+}
+
+#[test]
+fn twinkles_fade_out() {
+    let mut twinkle = Twinkle::new().with_rate(1000, 100);
+    let lit = |fb: &[Rgb]| fb.iter().filter(|px| **px != [0; 3]).count();
+    assert!(lit(&run_once(&mut twinkle, ctx(0, 30, 256))) > 0);
+    let mut quiet = Twinkle::new().with_rate(1000, 100);
+    run_once(&mut quiet, ctx(0, 30, 256));
+    let mut quiet = quiet.with_rate(0, 100);
+    let frames = run(&mut quiet, (1..20).map(|f| ctx(f, 30, 256)));
+    assert_eq!(lit(frames.last().unwrap()), 0, "all faded after 100 ms");
+}
+
+/// `ctx` with the music at `audio`.
+fn with_audio(audio: Audio, c: Ctx) -> Ctx {
+    Ctx { audio, ..c }
+}
+
+#[test]
+fn vu_bar_follows_the_level() {
+    let lit = |fb: &[Rgb]| fb.iter().filter(|px| **px != [0; 3]).count();
+    let half = Audio {
+        level: 32768,
+        ..Audio::SILENT
+    };
+    let mut vu = Spectrum::new();
+    assert_eq!(
+        lit(&run_once(&mut vu, with_audio(half, ctx(0, 20, 256)))),
+        10
+    );
+    // The bar falls (full scale in 500 ms); the peak dot holds for 500 ms.
+    let last = (1..50)
+        .map(|f| run_once(&mut vu, ctx(f, 20, 256)))
+        .last()
+        .unwrap();
+    assert_eq!(lit(&last), 1);
+    assert!(last[9] != [0; 3], "peak dot");
+}
+
+#[test]
+fn spectrum_lights_each_band_in_its_segment() {
+    let mut bands = [0; 8];
+    bands[2] = 65535;
+    let audio = Audio {
+        bands,
+        ..Audio::SILENT
+    };
+    let mut spectrum = Spectrum::new().with_mode(MODE_SPECTRUM);
+    let fb = run_once(&mut spectrum, with_audio(audio, ctx(0, 16, 256)));
+    for (i, px) in fb.iter().enumerate() {
+        assert_eq!(*px != [0; 3], (4..6).contains(&i), "LED {i}");
+    }
+}
+
+#[test]
+fn beats_flash_the_meter() {
+    let beat = Audio {
+        since_beat_ms: 0,
+        ..Audio::SILENT
+    };
+    let mut vu = Spectrum::new();
+    let fb = run_once(&mut vu, with_audio(beat, ctx(0, 10, 256)));
+    assert!(fb.iter().all(|px| *px != [0; 3]));
+    let fb = run_once(&mut vu, ctx(1, 10, 256));
+    assert!(
+        fb.iter().all(|px| *px == [0; 3]),
+        "flash is over with no beat"
+    );
+}
+
+#[test]
+fn quiet_meter_breathes_at_its_period() {
+    let lit = |fb: &[Rgb]| fb.iter().any(|px| *px != [0; 3]);
+    let mut vu = Spectrum::new();
+    vu.set_param(2, 1000);
+    // Dark until the music has been quiet for 2 s.
+    let frames = 2000 / REF_FRAME_MS;
+    for f in 0..frames - 1 {
+        assert!(!lit(&run_once(&mut vu, ctx(f, 10, 256))), "frame {f}");
+    }
+    // Then up to full half a period in.
+    let half = frames + 500 / REF_FRAME_MS;
+    let fb = (frames..=half)
+        .map(|f| run_once(&mut vu, ctx(f, 10, 256)))
+        .last()
+        .unwrap();
+    assert!(fb.iter().all(|px| px.iter().any(|&c| c > 60000)));
+    // Any sound brings the meter back.
+    let beat = Audio {
+        since_beat_ms: 0,
+        ..Audio::SILENT
+    };
+    run_once(&mut vu, with_audio(beat, ctx(half + 1, 10, 256)));
+    assert!(!lit(&run_once(&mut vu, ctx(half + 2, 10, 256))));
+}
+
+/// Tick + render once (no `on_enter`).
+fn run_once(effect: &mut dyn Effect, c: Ctx) -> Vec<Rgb> {
+    effect.tick(&c);
+    let mut fb = vec![[0; 3]; c.len];
+    effect.render(&mut fb);
+    fb
+}
@@ -0,0 +1,92 @@
+//! Matrix mapping and the 2D effects drawn through it.
+
+use led_fx::effects::occupancy::GRID_MAX;
+use led_fx::effects::{Ctx, Effect, Occupancy, Plasma2d};
+use led_fx::matrix::{Canvas, Layout, XyMap, NO_LED};
+use led_fx::palette::Palette;
+use led_fx::Rgb;
+
+const WHITE: Palette = Palette::solid([65535; 3]);
+
+fn render(effect: &mut dyn Effect, len: usize) -> Vec<Rgb> {
+    let ctx = Ctx {
+        dt_ms: 8,
+        len,
+        ..Ctx::default()
+    };
+    effect.on_enter(&ctx);
+    effect.tick(&ctx);
+    let mut fb = vec![[0; 3]; len];
+    effect.render(&mut fb);
+    fb
+}
+
+#[test]
+fn layouts_map_xy_to_leds() {
+    let rows = XyMap::new(4, 3, Layout::RowMajor);
+    let snake = XyMap::new(4, 3, Layout::Serpentine);
+    assert_eq!((rows.len(), snake.len()), (12, 12));
+    assert_eq!(rows.index(1, 1), Some(5));
+    // The second row runs back: (0, 1) is its last LED.
+    assert_eq!(snake.index(0, 1), Some(7));
+    assert_eq!(snake.index(3, 1), Some(4));
+    assert_eq!(snake.index(1, 2), Some(9));
+    assert_eq!(snake.index(4, 0), None);
+    assert_eq!(snake.index(0, 3), None);
+
+    // Column-wise wiring with a missing corner.
+    static LUT: [u16; 4] = [0, 2, 1, NO_LED];
+    let lut = XyMap::new(2, 2, Layout::Lut(&LUT));
+    assert_eq!(lut.len(), 3);
+    assert_eq!(lut.index(1, 0), Some(2));
+    assert_eq!(lut.index(0, 1), Some(1));
+    assert_eq!(lut.index(1, 1), None);
+}
+
+#[test]
+fn canvas_ignores_what_is_not_there() {
+    let map = XyMap::new(3, 3, Layout::Serpentine);
+    let mut fb = vec![[0; 3]; 5]; // shorter than the panel
+    let mut canvas = Canvas::new(map, &mut fb);
+    canvas.set(0, 1, [1, 2, 3]); // LED 5: past the frame
+    canvas.set(2, 1, [4, 5, 6]); // LED 3
+    canvas.set(7, 0, [7, 8, 9]); // off the panel
+    assert_eq!(canvas.get(0, 1), [0; 3]);
+    assert_eq!(canvas.get(2, 1), [4, 5, 6]);
+    assert_eq!(fb[3], [4, 5, 6]);
+}
+
+#[test]
+fn occupancy_fills_its_cells_and_scales() {
+    // Same size: exactly the full cells light, through the serpentine.
+    let map = XyMap::new(4, 2, Layout::Serpentine);
+    let mut grid = Occupancy::new(map).with_palette(WHITE);
+    grid.set_grid(4, &[0b0001, 0b0011]);
+    let fb = render(&mut grid, map.len());
+    let lit: Vec<usize> = (0..8).filter(|&i| fb[i] != [0; 3]).collect();
+    assert_eq!(lit, [0, 6, 7]);
+
+    // A 4x2 grid on a 2x1 panel: each LED covers 2x2 cells.
+    let small = XyMap::new(2, 1, Layout::RowMajor);
+    let mut grid = Occupancy::new(small).with_palette(WHITE);
+    grid.set_grid(4, &[0b0001, 0b1111]);
+    let fb = render(&mut grid, 2);
+    assert_eq!(fb[0], [49151; 3], "3 of 4 cells full");
+    assert_eq!(fb[1], [32767; 3]);
+
+    // Oversized grids are clipped, not a panic.
+    let mut grid = Occupancy::new(small);
+    grid.set_grid(64, &[u32::MAX; GRID_MAX + 4]);
+    render(&mut grid, 2);
+}
+
+#[test]
+fn plasma2d_covers_the_panel() {
+    let map = XyMap::new(8, 8, Layout::Serpentine);
+    let mut fb = render(&mut Plasma2d::new(map), map.len());
+    assert!(fb.iter().all(|px| *px != [0; 3]));
+    // Not the same colour everywhere: it varies in both directions.
+    let canvas = Canvas::new(map, &mut fb);
+    assert_ne!(canvas.get(0, 0), canvas.get(7, 0));
+    assert_ne!(canvas.get(0, 0), canvas.get(0, 7));
+}
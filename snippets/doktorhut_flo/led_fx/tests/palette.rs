@@ -0,0 +1,97 @@
+//! Palette lookups (stops at their indices, linear in between), blending and
+//! the themes.
+
+use led_fx::color::rgb;
+use led_fx::effects::Ctx;
+use led_fx::palette::{
+    Palette, PaletteFade, Theme, Themed, BEER_AMBER, HEAT, LAVA, OCEAN, RAINBOW, STOPS,
+};
+use led_fx::transition::Transition;
+
+#[test]
+fn ends_are_the_first_and_last_stops() {
+    assert_eq!(HEAT.at(0), [0; 3]);
+    assert_eq!(HEAT.at(255), [65535; 3]);
+    assert_eq!(RAINBOW.at(0), rgb(255, 0, 0));
+}
+
+#[test]
+fn interpolates_between_stops() {
+    let mut stops = [[0; 3]; STOPS];
+    stops[1] = [1600, 0, 0];
+    let p = Palette::new(stops);
+    // Stop 1 sits at index 17; halfway to it is half its value.
+    assert_eq!(p.at(17), [1600, 0, 0]);
+    let half = p.at(8)[0] as i32 + p.at(9)[0] as i32;
+    assert!((half - 1600).abs() <= 100, "{half}");
+    // Monotonic on the way up.
+    let up: Vec<u16> = (0..=17).map(|i| p.at(i)[0]).collect();
+    assert!(up.windows(2).all(|w| w[0] <= w[1]), "{up:?}");
+}
+
+#[test]
+fn solid_is_one_colour() {
+    let p = Palette::solid(rgb(10, 20, 30));
+    assert!((0..=255).all(|i| p.at(i) == rgb(10, 20, 30)));
+}
+
+#[test]
+fn gradient_hits_its_anchors() {
+    let p = Palette::gradient(&[(0, [0; 3]), (255, [65535, 0, 15000])]);
+    assert_eq!(p.at(0), [0; 3]);
+    assert_eq!(p.at(255), [65535, 0, 15000]);
+    // Two anchors: a straight line.
+    let mid = p.at(128)[0] as i32;
+    assert!((mid - 32896).abs() < 300, "{mid}");
+}
+
+#[test]
+fn themed_follows_the_theme_unless_pinned() {
+    let ctx = Ctx {
+        palette: OCEAN,
+        ..Ctx::default()
+    };
+    let mut themed = Themed::new();
+    themed.resolve(&ctx);
+    assert_eq!(*themed, OCEAN);
+    themed.pin(LAVA);
+    themed.resolve(&ctx);
+    assert_eq!(themed.at(0), LAVA.at(0));
+}
+
+#[test]
+fn blend_goes_from_one_palette_to_the_other() {
+    assert_eq!(LAVA.blend(&OCEAN, 0), LAVA);
+    assert_eq!(LAVA.blend(&OCEAN, 65535), OCEAN);
+}
+
+#[test]
+fn fade_blends_over_its_duration() {
+    let mut fade = PaletteFade::new(LAVA);
+    fade.set_target(OCEAN, Transition::crossfade(100));
+    assert_eq!(fade.current(), LAVA);
+    fade.tick(50);
+    assert_eq!(fade.current(), LAVA.blend(&OCEAN, 32767));
+    // Retargeting to where it is heading changes nothing.
+    fade.set_target(OCEAN, Transition::crossfade(100));
+    fade.tick(50);
+    assert_eq!(fade.current(), OCEAN);
+
+    // A new target mid-blend starts from what is showing.
+    fade.set_target(LAVA, Transition::crossfade(100));
+    fade.tick(20);
+    fade.set_target(BEER_AMBER, Transition::crossfade(100));
+    assert_eq!(fade.current(), OCEAN.blend(&LAVA, 13107));
+    fade.set_target(HEAT, Transition::CUT);
+    assert_eq!(fade.current(), HEAT);
+}
+
+#[test]
+fn themes_have_distinct_names_and_palettes() {
+    for (i, a) in Theme::ALL.iter().enumerate() {
+        for b in &Theme::ALL[i + 1..] {
+            assert_ne!(a.name(), b.name());
+            assert_ne!(a.palette(), b.palette());
+        }
+    }
+}
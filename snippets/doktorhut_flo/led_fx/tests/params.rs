@@ -0,0 +1,116 @@
+//! Tunable effect parameters: ranges, clamping and what they change.
+
+use led_fx::effects::source::{Gaps, GAP_MAX, GAP_MIN};
+use led_fx::effects::{BeerByte, Breathe, Ctx, Effect, Packet, Param, Party, Spectrum, Stream};
+
+fn ctx(frame: u32, len: usize) -> Ctx {
+    Ctx {
+        now_ms: frame * 8,
+        dt_ms: 8,
+        len,
+        velocity_q8: 256,
+        accel: [0.0, 0.0, 1.0],
+        ..Ctx::default()
+    }
+}
+
+#[test]
+fn stepping_stays_in_range() {
+    let p = Param::new("x", 1, 10, 4);
+    assert_eq!(p.stepped(1, true), 5);
+    assert_eq!(p.stepped(9, true), 10);
+    assert_eq!(p.stepped(3, false), 1);
+    assert_eq!(p.clamp(-7), 1);
+}
+
+#[test]
+fn effects_clamp_their_params() {
+    let mut effects: [Box<dyn Effect>; 6] = [
+        Box::new(Stream::new(30)),
+        Box::new(Packet::new(0)),
+        Box::new(BeerByte::new(0)),
+        Box::new(Party::new(0, false)),
+        Box::new(Breathe::new([65535; 3], 2000, 0)),
+        Box::new(Spectrum::new()),
+    ];
+    for e in effects.iter_mut() {
+        for (i, p) in e.params().iter().enumerate() {
+            assert!(p.min <= e.param(i) && e.param(i) <= p.max, "{}", p.name);
+            e.set_param(i, p.max + 1000);
+            assert_eq!(e.param(i), p.max, "{}", p.name);
+            e.set_param(i, p.min - 1000);
+            assert_eq!(e.param(i), p.min, "{}", p.name);
+        }
+        let n = e.params().len();
+        e.set_param(n, 5); // past the list: ignored
+        assert_eq!(e.param(n), 0);
+    }
+}
+
+#[test]
+fn gaps_stretch_the_source_range() {
+    let gaps = Gaps { min: 4, max: 16 };
+    assert_eq!(gaps.scale(GAP_MIN), 4);
+    assert_eq!(gaps.scale(GAP_MAX), 16);
+    assert_eq!(gaps.scale(0), 4);
+    // Either order works.
+    assert_eq!(Gaps { min: 16, max: 4 }.scale(GAP_MAX), 16);
+    assert_eq!(Gaps::default().scale(5), 5);
+}
+
+#[test]
+fn wider_bits_stretch_the_beer_shot() {
+    // One byte, 2 LEDs/bit: 16 cells, so it takes 8 frames more to leave.
+    let frames_to_finish = |width: i32| {
+        let mut beer = BeerByte::new(0xA5);
+        beer.set_param(0, width);
+        beer.on_enter(&ctx(0, 20));
+        (0..)
+            .take_while(|&f| {
+                beer.tick(&ctx(f, 20));
+                !beer.is_finished()
+            })
+            .count()
+    };
+    assert_eq!(frames_to_finish(2), frames_to_finish(1) + 8);
+}
+
+#[test]
+fn party_scroll_rate_sets_the_speed() {
+    let render_after = |rate: i32, frames: u32| {
+        let mut party = Party::new(0, false);
+        party.set_param(0, rate);
+        for f in 0..frames {
+            party.tick(&ctx(f, 16));
+        }
+        let mut fb = vec![[0; 3]; 16];
+        party.render(&mut fb);
+        fb
+    };
+    // Twice the rate covers the same ground in half the time...
+    assert!(render_after(250, 50) == render_after(125, 100));
+    // ...and a rate of 0 stands still.
+    assert!(render_after(0, 50) == render_after(0, 1));
+}
+
+#[test]
+fn new_bit_width_restarts_the_packet() {
+    let lit = |p: &Packet| {
+        let mut fb = vec![[0; 3]; 20];
+        p.render(&mut fb);
+        fb.iter().filter(|&&px| px != [0; 3]).count()
+    };
+    let mut packet = Packet::new(0xFF);
+    for f in 0..12 {
+        packet.tick(&ctx(f, 20));
+    }
+    assert!(lit(&packet) > 0);
+    // The same width again changes nothing...
+    packet.set_param(0, 1);
+    assert!(lit(&packet) > 0);
+    // ...a new one starts the (now wider) byte over, just off the strip.
+    packet.set_param(0, 2);
+    assert_eq!(lit(&packet), 0);
+    packet.tick(&ctx(12, 20));
+    assert_eq!(lit(&packet), 1);
+}
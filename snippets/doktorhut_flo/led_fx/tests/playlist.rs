@@ -0,0 +1,45 @@
+//! Playlists: timing, wrap-around and long ticks.
+
+use led_fx::playlist::{Entry, Playlist};
+use led_fx::transition::Transition;
+
+const FADE: Transition = Transition::crossfade(100);
+static ENTRIES: [Entry<char>; 3] = [
+    Entry::new('a', 1000, FADE),
+    Entry::new('b', 500, FADE),
+    Entry::new('c', 2000, FADE),
+];
+
+fn item(p: &Playlist<char>) -> char {
+    p.current().unwrap().item
+}
+
+#[test]
+fn plays_each_entry_for_its_time_and_loops() {
+    let mut p = Playlist::new(&ENTRIES);
+    assert_eq!(item(&p), 'a');
+    p.tick(999);
+    assert_eq!(item(&p), 'a');
+    p.tick(1);
+    assert_eq!(item(&p), 'b');
+    p.tick(500);
+    assert_eq!(item(&p), 'c');
+    p.tick(2000);
+    assert_eq!(item(&p), 'a', "wraps around");
+}
+
+#[test]
+fn a_long_tick_skips_ahead() {
+    let mut p = Playlist::new(&ENTRIES);
+    p.tick(1000 + 500 + 2000 + 1200); // a whole loop, then into b
+    assert_eq!(item(&p), 'b');
+    p.restart();
+    assert_eq!(item(&p), 'a');
+}
+
+#[test]
+fn an_empty_playlist_plays_nothing() {
+    let mut p: Playlist<char> = Playlist::new(&[]);
+    p.tick(100);
+    assert!(p.current().is_none());
+}
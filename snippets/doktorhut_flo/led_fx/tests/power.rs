@@ -0,0 +1,55 @@
+//! Power limiter: scaled frames stay within the budget.
+
+use led_fx::power::{estimate_ma, idle_ma, scale_q16};
+
+/// Scale a frame of one-wire bytes by the limiter's factor (the drivers fold
+/// it into the brightness, to the same effect) and return its estimated draw.
+fn limited_ma(wire: &mut [u8], leds: usize, budget_ma: u32) -> u32 {
+    let sum = |w: &[u8]| w.iter().map(|&b| b as u32).sum::<u32>();
+    let scale = scale_q16(estimate_ma(sum(wire), leds), idle_ma(leds), budget_ma);
+    if scale < 65536 {
+        for b in wire.iter_mut() {
+            *b = ((*b as u32 * scale) >> 16) as u8;
+        }
+    }
+    estimate_ma(sum(wire), leds)
+}
+
+#[test]
+fn limited_frame_stays_within_budget() {
+    // 300 LEDs: 300 mA idle, up to 18 A at full white.
+    for (level, budget) in [
+        (255u8, 1500),
+        (255, 500),
+        (128, 1500),
+        (40, 1000),
+        (255, 301),
+    ] {
+        let mut wire = [level; 300 * 3];
+        let draw = limited_ma(&mut wire, 300, budget);
+        assert!(
+            draw <= budget,
+            "{draw} mA over {budget} mA at level {level}"
+        );
+        // ...and uses most of it (not scaled as if idle current could dim),
+        // short of rounding each byte down (up to 900 * 20 / 255 mA).
+        assert!(
+            draw + 71 >= budget.min(estimate_ma(level as u32 * 900, 300)),
+            "{draw} mA of {budget} mA"
+        );
+    }
+}
+
+#[test]
+fn frames_within_budget_are_left_alone() {
+    assert_eq!(scale_q16(800, 300, 1500), 65536);
+    let mut wire = [10u8; 300 * 3];
+    let before = estimate_ma(10 * 900, 300);
+    assert_eq!(limited_ma(&mut wire, 300, 1500), before);
+    assert!(wire.iter().all(|&b| b == 10));
+}
+
+#[test]
+fn idle_over_budget_blanks_the_frame() {
+    assert_eq!(scale_q16(2000, 300, 200), 0);
+}
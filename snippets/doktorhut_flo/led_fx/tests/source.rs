@@ -0,0 +1,40 @@
+//! Byte sources: message order and the lock-free bus tap.
+
+use led_fx::effects::source::{ByteSource, ByteTap, Text, GAP_MAX, GAP_MIN};
+
+fn drain(src: &mut impl ByteSource) -> Vec<u8> {
+    std::iter::from_fn(|| src.next_byte()).collect()
+}
+
+#[test]
+fn text_repeats_with_a_pause() {
+    let mut text = Text::new(b"hi");
+    assert_eq!(text.next_byte(), Some(b'h'));
+    assert_eq!(text.gap(), GAP_MIN);
+    assert_eq!(text.next_byte(), Some(b'i'));
+    assert_eq!(text.gap(), GAP_MAX);
+    assert_eq!(text.next_byte(), Some(b'h'));
+}
+
+#[test]
+fn tap_delivers_in_order_to_every_reader() {
+    static TAP: ByteTap<8> = ByteTap::new();
+    TAP.push(&[0xAA]); // before the readers: not seen
+    let (mut a, mut b) = (TAP.reader(), TAP.reader());
+    assert_eq!(a.next_byte(), None);
+    TAP.push(&[0xD0, 0x3B]);
+    TAP.push(&[0xD1, 1, 2]);
+    assert_eq!(drain(&mut a), [0xD0, 0x3B, 0xD1, 1, 2]);
+    assert_eq!(drain(&mut b), [0xD0, 0x3B, 0xD1, 1, 2]);
+    assert_eq!(a.next_byte(), None);
+}
+
+#[test]
+fn lagging_reader_skips_to_the_newest_bytes() {
+    static TAP: ByteTap<4> = ByteTap::new();
+    let mut r = TAP.reader();
+    TAP.push(&[1, 2, 3, 4, 5, 6]);
+    assert_eq!(drain(&mut r), [3, 4, 5, 6]);
+    TAP.push(&[7]);
+    assert_eq!(drain(&mut r), [7]);
+}
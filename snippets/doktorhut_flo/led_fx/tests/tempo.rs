@@ -0,0 +1,68 @@
+//! Tempo clock: beat phase and bar position, nudging, and tap tempo.
+
+use led_fx::tempo::{TapTempo, Tempo, BPM_MAX, BPM_MIN};
+
+#[test]
+fn beats_and_bars_follow_the_clock() {
+    let t = Tempo::from_bpm(120, 1000);
+    assert_eq!((t.beat_ms, t.bpm()), (500, 120));
+    let b = t.at(1000);
+    assert_eq!((b.beat, b.phase_q16), (0, 0));
+    assert!(b.is_downbeat());
+    assert_eq!(b.pulse_q16(), 65535);
+    let b = t.at(1000 + 3 * 500 + 250);
+    assert_eq!((b.beat, b.phase_q16), (3, 32768));
+    assert_eq!(b.bar_phase_q16(), (3 * 65536 + 32768) / 4);
+    assert!(b.pulse_q16() < 20000, "pulse falls through the beat");
+    // The next bar starts over.
+    assert_eq!(t.at(1000 + 4 * 500).beat, 0);
+    assert_eq!(Tempo::from_bpm(1000, 0).bpm(), BPM_MAX);
+    assert_eq!(Tempo::from_bpm(1, 0).bpm(), BPM_MIN);
+}
+
+#[test]
+fn nudging_keeps_the_place_in_the_bar() {
+    let t = Tempo::from_bpm(120, 0);
+    let now = 10_000 + 2 * 500 + 125; // beat 2, a quarter in
+    let nudged = t.with_bpm(100, now);
+    assert_eq!(nudged.bpm(), 100);
+    let (before, after) = (t.at(now), nudged.at(now));
+    assert_eq!(before.beat, after.beat);
+    assert!(before.phase_q16.abs_diff(after.phase_q16) < 200);
+    // ...and then beats at the new rate.
+    assert_eq!(nudged.at(now + 3 * 600).beat, 1);
+}
+
+#[test]
+fn taps_set_the_tempo_and_the_downbeat() {
+    let mut taps = TapTempo::new();
+    assert_eq!(taps.tap(5000), None, "one tap is no tempo");
+    // Slightly uneven taps average out.
+    let mut tempo = None;
+    for (i, jitter) in [10i32, -8, 5, -4, 0].into_iter().enumerate() {
+        tempo = taps.tap((5000 + (i as i32 + 1) * 500 + jitter) as u32);
+    }
+    let tempo = tempo.unwrap();
+    assert_eq!(tempo.bpm(), 120);
+    // The run's first tap was a downbeat; the sixth tap is beat 1 of bar 2.
+    let last_tap = 5000 + 6 * 500 - 500;
+    let b = tempo.at(last_tap);
+    assert_eq!(b.beat, 1);
+    assert!(b.phase_q16 < 1000 || b.phase_q16 > 64535);
+}
+
+#[test]
+fn a_pause_starts_a_new_run_of_taps() {
+    let mut taps = TapTempo::new();
+    taps.tap(0);
+    assert_eq!(taps.tap(1000).map(|t| t.bpm()), Some(60));
+    // Long after: a fresh run at a new tempo, downbeat on its first tap.
+    assert_eq!(taps.tap(10_000), None);
+    let tempo = taps.tap(10_400).unwrap();
+    assert_eq!(tempo.bpm(), 150);
+    assert_eq!(tempo.at(10_000).beat, 0);
+    // Taps too fast for the range are clamped.
+    let mut taps = TapTempo::new();
+    taps.tap(0);
+    assert_eq!(taps.tap(100).map(|t| t.bpm()), Some(BPM_MAX));
+}
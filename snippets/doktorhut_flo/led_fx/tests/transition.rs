@@ -0,0 +1,109 @@
+//! Transitions: progress, and the frames a crossfade or wipe draws on the way
+//! from one effect to the next (or to dark).
+
+use led_fx::compose::{Compositor, Layer};
+use led_fx::effects::{Ctx, Effect};
+use led_fx::transition::{Fade, Transition};
+use led_fx::{Framebuffer, Rgb, MAX_LEDS};
+
+const RED: Rgb = [65535, 0, 0];
+const BLUE: Rgb = [0, 0, 65535];
+const DARK: Rgb = [0; 3];
+const LEN: usize = 8;
+
+struct Solid(Rgb);
+
+impl Effect for Solid {
+    fn tick(&mut self, _ctx: &Ctx) {}
+
+    fn render(&self, fb: &mut Framebuffer) {
+        fb.fill(self.0);
+    }
+}
+
+/// A `duration_ms` fade ticked `elapsed_ms` in.
+fn at(transition: Transition, elapsed_ms: u32) -> Fade {
+    let mut fade = Fade::new(transition);
+    fade.tick(elapsed_ms);
+    fade
+}
+
+/// The frame `fade` draws from `from` to `to` (`None`: dark), layered the way
+/// the LED task does: outgoing underneath, incoming on top.
+fn frame(fade: &Fade, from: Option<Rgb>, to: Option<Rgb>) -> Vec<Rgb> {
+    let mut scratch = Box::new([[0; 3]; MAX_LEDS]);
+    let mut comp = Compositor::with_scratch(&mut scratch);
+    let mut fb = vec![DARK; LEN];
+    let ctx = Ctx {
+        len: LEN,
+        ..Ctx::default()
+    };
+    let outgoing = from.map(|c| (c, fade.outgoing(to.is_some())));
+    let incoming = to.map(|c| (c, fade.incoming()));
+    for (color, (opacity_q16, mask)) in outgoing.into_iter().chain(incoming) {
+        let mut solid = Solid(color);
+        let layer = Layer::new(&mut solid)
+            .with_opacity_q16(opacity_q16)
+            .with_mask(mask);
+        comp.draw(layer, &ctx, &mut fb);
+    }
+    fb
+}
+
+#[test]
+fn progress_runs_from_start_to_done() {
+    let fade = at(Transition::crossfade(400), 0);
+    assert_eq!(fade.progress_q16(), 0);
+    assert!(!fade.is_done());
+    assert_eq!(at(Transition::crossfade(400), 100).progress_q16(), 16383);
+    // Ticking past the end holds at done.
+    let fade = at(Transition::crossfade(400), 1000);
+    assert_eq!(fade.progress_q16(), 65535);
+    assert!(fade.is_done());
+    // A cut is done before it starts.
+    let cut = Fade::new(Transition::CUT);
+    assert_eq!(cut.progress_q16(), 65535);
+    assert!(cut.is_done());
+}
+
+#[test]
+fn crossfade_mixes_outgoing_into_incoming() {
+    let t = Transition::crossfade(400);
+    let red_to_blue = |ms| frame(&at(t, ms), Some(RED), Some(BLUE));
+    assert_eq!(red_to_blue(0), [RED; LEN]);
+    assert_eq!(red_to_blue(200), [[32768, 0, 32767]; LEN]);
+    assert_eq!(red_to_blue(400), [BLUE; LEN]);
+    // To and from dark, the one effect fades by itself.
+    assert_eq!(frame(&at(t, 200), Some(RED), None), [[32768, 0, 0]; LEN]);
+    assert_eq!(frame(&at(t, 200), None, Some(BLUE)), [[0, 0, 32767]; LEN]);
+}
+
+#[test]
+fn wipe_sweeps_in_from_the_data_in_end() {
+    let t = Transition::wipe(400);
+    let red_to_blue = |ms| frame(&at(t, ms), Some(RED), Some(BLUE));
+    assert_eq!(red_to_blue(0), [RED; LEN]);
+    // A quarter in: the front two LEDs, hard edged (no partial pixel).
+    let quarter = [&[BLUE; 2][..], &[RED; 6]].concat();
+    assert_eq!(red_to_blue(100), quarter);
+    assert_eq!(red_to_blue(200), [&[BLUE; 4][..], &[RED; 4]].concat());
+    assert_eq!(red_to_blue(400), [BLUE; LEN]);
+    // Wiping to dark takes the outgoing effect away from the same end.
+    let to_dark = frame(&at(t, 100), Some(RED), None);
+    assert_eq!(to_dark, [&[DARK; 2][..], &[RED; 6]].concat());
+}
+
+#[test]
+fn finished_fade_hands_the_strip_to_the_incoming_effect() {
+    for t in [Transition::crossfade(400), Transition::wipe(400)] {
+        let done = at(t, 400);
+        assert!(done.is_done());
+        // The incoming layer alone draws the whole strip, as it will once
+        // the fade is dropped...
+        assert_eq!(frame(&done, None, Some(BLUE)), [BLUE; LEN]);
+        // ...and the outgoing one has nothing left to show.
+        assert_eq!(frame(&done, Some(RED), None), [DARK; LEN]);
+        // Before that, the outgoing layer still draws in full.
+        assert_eq!(frame(&at(t, 0), Some(RED), None), [RED; LEN]);
+    }
+}
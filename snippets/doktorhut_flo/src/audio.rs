@@ -0,0 +1,135 @@
+//! Audio input for the MUSIC strip: an electret mic module (or the DFPlayer's
+//! DAC output through a coupling cap and a mid-rail bias) on GPIO34 / ADC1,
+//! sampled at `SAMPLE_RATE` and analysed by `led_fx::audio`. Each block's
+//! band levels, level and beats go to `control` for the LED task.
+//!
+//! The samples are taken by a TIMG0 interrupt, not a task: an executor tick
+//! comes late whenever another task on the core is busy (the display flush),
+//! which smears the FFT. Each interrupt collects the conversion the previous
+//! one started and starts the next, so the ADC samples at the timer's even
+//! spacing and the handler never waits on it. Full blocks go to [`run`]
+//! through a channel for the analysis.
+
+use core::cell::RefCell;
+
+use critical_section::Mutex;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::channel::Channel;
+use embassy_time::{with_timeout, Duration, Instant, Timer};
+use esp_hal::analog::adc::{Adc, AdcPin};
+use esp_hal::handler;
+use esp_hal::interrupt::Priority;
+use esp_hal::peripherals::{ADC1, GPIO34};
+use esp_hal::timer::PeriodicTimer;
+use esp_hal::Blocking;
+use led_fx::audio::SAMPLE_RATE;
+
+use crate::control;
+pub use led_fx::audio::Analyser;
+
+pub type AudioAdc = Adc<'static, ADC1<'static>, Blocking>;
+pub type AudioPin = AdcPin<GPIO34<'static>, ADC1<'static>>;
+
+// 12-bit reading around the mid-rail bias, scaled up to the i16 range the
+// analyser expects (it removes what is left of the DC offset per block).
+const ADC_MID: i32 = 2048;
+const ADC_SHIFT: u32 = 4;
+
+// Samples per hand-off (8 ms at 8 kHz). A few blocks of slack cover the
+// analysis of a full FFT window; past that the newest block is dropped.
+const BLOCK: usize = 64;
+static BLOCKS: Channel<CriticalSectionRawMutex, [i16; BLOCK], 4> = Channel::new();
+
+struct Sampler {
+    adc: AudioAdc,
+    pin: AudioPin,
+    timer: PeriodicTimer<'static, Blocking>,
+    block: [i16; BLOCK],
+    fill: usize,
+}
+
+static SAMPLER: Mutex<RefCell<Option<Sampler>>> = Mutex::new(RefCell::new(None));
+
+/// Hand the ADC and a free timer to the sampling interrupt (stopped until
+/// [`run`] sees MUSIC playing). Call on the core the interrupt should run on.
+pub fn init(adc: AudioAdc, pin: AudioPin, mut timer: PeriodicTimer<'static, Blocking>) {
+    timer.set_interrupt_handler(sample_isr);
+    critical_section::with(|cs| {
+        *SAMPLER.borrow_ref_mut(cs) = Some(Sampler {
+            adc,
+            pin,
+            timer,
+            block: [0; BLOCK],
+            fill: 0,
+        });
+    });
+}
+
+/// Start or stop the sampling interrupt.
+fn sampling(on: bool) {
+    critical_section::with(|cs| {
+        let mut sampler = SAMPLER.borrow_ref_mut(cs);
+        let Some(s) = sampler.as_mut() else {
+            return;
+        };
+        if on {
+            s.fill = 0;
+            let period = esp_hal::time::Duration::from_micros(1_000_000 / SAMPLE_RATE as u64);
+            s.timer.start(period).ok();
+            s.timer.enable_interrupt(true);
+        } else {
+            s.timer.enable_interrupt(false);
+            s.timer.stop();
+        }
+    });
+}
+
+// Above the executors (thread mode), so a busy task cannot delay a sample.
+#[handler(priority = Priority::Priority2)]
+fn sample_isr() {
+    critical_section::with(|cs| {
+        let mut sampler = SAMPLER.borrow_ref_mut(cs);
+        let Some(s) = sampler.as_mut() else {
+            return;
+        };
+        s.timer.clear_interrupt();
+        // The conversion started one period ago is done by now.
+        if let Ok(raw) = s.adc.read_oneshot(&mut s.pin) {
+            s.block[s.fill] = ((raw as i32 - ADC_MID) << ADC_SHIFT).clamp(-32768, 32767) as i16;
+            s.fill += 1;
+            if s.fill == BLOCK {
+                BLOCKS.try_send(s.block).ok();
+                s.fill = 0;
+            }
+        }
+        // Start the next one (answers WouldBlock).
+        s.adc.read_oneshot(&mut s.pin).ok();
+    });
+}
+
+#[embassy_executor::task]
+pub async fn run(analyser: &'static mut Analyser) {
+    loop {
+        // Only sampled while MUSIC plays: 8k interrupts a second are not free.
+        if !control::music_on() {
+            control::clear_audio();
+            Timer::after(Duration::from_millis(50)).await;
+            continue;
+        }
+        sampling(true);
+        while control::music_on() {
+            // Time out now and then to notice MUSIC stopping.
+            let Ok(block) = with_timeout(Duration::from_millis(50), BLOCKS.receive()).await else {
+                continue;
+            };
+            for sample in block {
+                if let Some(analysis) = analyser.push(sample) {
+                    control::set_audio(&analysis, Instant::now().as_millis() as u32);
+                }
+            }
+        }
+        sampling(false);
+        // Stale blocks would start the next song with a burst of the old one.
+        while BLOCKS.try_receive().is_ok() {}
+    }
+}
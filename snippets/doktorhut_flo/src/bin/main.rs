@@ -6,17 +6,21 @@
     holding buffers for the duration of a data transfer."
 )]
 
-use doktorhut_flo::bus::SharedBus;
-use doktorhut_flo::{dfplayer, display, imu, led_strip, rotary, servo};
+use doktorhut_flo::bus::{SharedBus, Tapped};
+use doktorhut_flo::led_strip::matrix::{Layout, XyMap};
+use doktorhut_flo::led_strip::{StripConfig, StripRole};
+use doktorhut_flo::{audio, dfplayer, display, imu, led_strip, rotary, servo};
 use embassy_embedded_hal::shared_bus::asynch::i2c::I2cDevice;
 use embassy_executor::Spawner;
 use embassy_sync::mutex::Mutex;
 use embassy_time::{Duration, Timer};
+use esp_hal::analog::adc::{Adc, AdcConfig, Attenuation};
 use esp_hal::clock::CpuClock;
 use esp_hal::gpio::{Input, InputConfig, Level, Output, OutputConfig, Pull};
 use esp_hal::system::{AppCoreGuard, CpuControl, Stack};
 use esp_hal::time::Rate;
 use esp_hal::timer::timg::TimerGroup;
+use esp_hal::timer::PeriodicTimer;
 use esp_hal_embassy::Executor;
 use esp_println::logger::init_logger;
 use static_cell::StaticCell;
@@ -30,6 +34,14 @@ fn panic(info: &core::panic::PanicInfo) -> ! {
 
 esp_bootloader_esp_idf::esp_app_desc!();
 
+// One record per physical strip (role, length, mounting, brightness cap).
+const STRIP_BEER: StripConfig = StripConfig::new(StripRole::Beer, 60);
+const STRIP_IMU: StripConfig = StripConfig::new(StripRole::Imu, 60);
+const STRIP_MUSIC: StripConfig = StripConfig::new(StripRole::Music, 60);
+// 16x16 WS2812 panel, rows zig-zagging from the top left.
+const PANEL: StripConfig = StripConfig::new(StripRole::Matrix, 0)
+    .with_matrix(XyMap::new(16, 16, Layout::Serpentine));
+
 #[embassy_executor::task]
 async fn blink_led(mut led: Output<'static>) {
     loop {
@@ -92,7 +104,8 @@ async fn main(spawner: Spawner) {
     static I2C_BUS: StaticCell<SharedBus> = StaticCell::new();
     let bus = I2C_BUS.init(Mutex::new(i2c));
     spawner.spawn(display::run(I2cDevice::new(bus))).ok();
-    spawner.spawn(imu::run(I2cDevice::new(bus))).ok();
+    // The MPU6050 reads are what the IMU LED strip streams.
+    spawner.spawn(imu::run(Tapped::new(I2cDevice::new(bus)))).ok();
 
     // DFPlayer Mini on UART1 (TX=GPIO17 -> DFPlayer RX, RX=GPIO16 <- DFPlayer TX).
     let uart = esp_hal::uart::Uart::new(
@@ -105,6 +118,19 @@ async fn main(spawner: Spawner) {
     .into_async();
     spawner.spawn(dfplayer::run(uart)).ok();
 
+    // Audio for the MUSIC strip: mic module / DFPlayer DAC tap on GPIO34
+    // (ADC1, 11 dB for the full 0..3.3V swing around the mid-rail bias),
+    // sampled from a TIMG0 interrupt. The analyser's FFT buffers live in
+    // static memory, not the task arena.
+    let mut adc_config = AdcConfig::new();
+    let audio_pin = adc_config.enable_pin(peripherals.GPIO34, Attenuation::_11dB);
+    let adc = Adc::new(peripherals.ADC1, adc_config);
+    let sample_timer = TimerGroup::new(peripherals.TIMG0);
+    audio::init(adc, audio_pin, PeriodicTimer::new(sample_timer.timer0));
+    static ANALYSER: StaticCell<audio::Analyser> = StaticCell::new();
+    let analyser = ANALYSER.init(audio::Analyser::new());
+    spawner.spawn(audio::run(analyser)).ok();
+
     // STS3215 servo on UART2 / GPIO14 (1 Mbps, TX only). BEER triggers a pour.
     let servo_tx = esp_hal::uart::UartTx::new(
         peripherals.UART2,
@@ -115,14 +141,38 @@ async fn main(spawner: Spawner) {
     .into_async();
     spawner.spawn(servo::run(servo_tx)).ok();
 
-    // The LED strip writes are blocking busy-waits; run them on the second core
-    // (APP core) so they never starve the UI tasks above on core0.
+    // One render loop drives all strips in lock-step: each frame is rendered
+    // for the same timestamp and the strips transmit in parallel (the RMT
+    // interrupt streams them out). It runs on the second core (APP core) to keep
+    // the per-frame render work off the UI tasks above on core0.
     // Each strip is dedicated to one process: BEER (GPIO25), IMU (GPIO32),
-    // MUSIC (GPIO33).
+    // MUSIC (GPIO33), and the matrix panel (GPIO26) shows the FLUIDS water; see
+    // the STRIP_* / PANEL records above for everything else.
+    // Dithering keeps the dim stream bits / fading music bars alive at low brightness.
     let rmt = led_strip::new_rmt(peripherals.RMT);
-    let strip_beer = led_strip::Ws2812::new(rmt.channel0, peripherals.GPIO25);
-    let strip_imu = led_strip::Ws2812::new(rmt.channel2, peripherals.GPIO32);
-    let strip_music = led_strip::Ws2812::new(rmt.channel4, peripherals.GPIO33);
+    let strip_beer = led_strip::Ws2812::new(rmt.channel0, peripherals.GPIO25, STRIP_BEER.len)
+        .with_dither(true);
+    let strip_imu = led_strip::Ws2812::new(rmt.channel2, peripherals.GPIO32, STRIP_IMU.len)
+        .with_dither(true);
+    let strip_music = led_strip::Ws2812::new(rmt.channel4, peripherals.GPIO33, STRIP_MUSIC.len)
+        .with_dither(true);
+    // The panel is driven like any strip; its layout only matters to effects.
+    let panel = led_strip::Ws2812::new(rmt.channel6, peripherals.GPIO26, PANEL.len)
+        .with_dither(true);
+    // Framebuffers and effect state live in static memory, not the task arena.
+    // Each renderer is ~12 KB, so they are written into place one at a time
+    // rather than built as one array on the stack.
+    static STRIPS: StaticCell<[led_strip::Renderer; 4]> = StaticCell::new();
+    let strips = STRIPS.uninit();
+    let slot = strips.as_mut_ptr().cast::<led_strip::Renderer>();
+    // SAFETY: all four slots are written before the array is assumed init.
+    let strips = unsafe {
+        slot.write(led_strip::Renderer::new(strip_beer.into(), STRIP_BEER));
+        slot.add(1).write(led_strip::Renderer::new(strip_imu.into(), STRIP_IMU));
+        slot.add(2).write(led_strip::Renderer::new(strip_music.into(), STRIP_MUSIC));
+        slot.add(3).write(led_strip::Renderer::new(panel.into(), PANEL));
+        strips.assume_init_mut()
+    };
 
     static APP_CORE_STACK: StaticCell<Stack<8192>> = StaticCell::new();
     static APP_EXECUTOR: StaticCell<Executor> = StaticCell::new();
@@ -134,10 +184,7 @@ async fn main(spawner: Spawner) {
         .start_app_core(stack, move || {
             let executor = APP_EXECUTOR.init(Executor::new());
             executor.run(|spawner| {
-                use led_strip::StripRole;
-                spawner.spawn(led_strip::run(strip_beer, StripRole::Beer)).ok();
-                spawner.spawn(led_strip::run(strip_imu, StripRole::Imu)).ok();
-                spawner.spawn(led_strip::run(strip_music, StripRole::Music)).ok();
+                spawner.spawn(led_strip::run(strips)).ok();
             });
         })
         .unwrap();
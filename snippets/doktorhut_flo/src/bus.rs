@@ -1,10 +1,56 @@
 //! Shared I2C bus: one mutex-guarded bus, an `I2cDevice` handle per task.
+//! Devices can be tapped: their traffic is copied into [`I2C_TAP`] for the LED
+//! stream to show.
 
 use embassy_embedded_hal::shared_bus::asynch::i2c::I2cDevice;
 use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
 use embassy_sync::mutex::Mutex;
+use embedded_hal_async::i2c::{ErrorType, I2c as AsyncI2c, Operation};
 use esp_hal::i2c::master::I2c;
 use esp_hal::Async;
+use led_fx::effects::source::ByteTap;
 
 pub type SharedBus = Mutex<CriticalSectionRawMutex, I2c<'static, Async>>;
 pub type SharedI2c = I2cDevice<'static, CriticalSectionRawMutex, I2c<'static, Async>>;
+pub type TappedI2c = Tapped<SharedI2c>;
+
+// A few transactions' worth; a slow LED stream skips to the newest bytes.
+pub const I2C_TAP_LEN: usize = 32;
+/// Bytes of the completed transactions of all tapped devices, as on the wire
+/// (marked where the STARTs and STOPs go).
+pub static I2C_TAP: ByteTap<I2C_TAP_LEN> = ByteTap::new();
+
+/// An I2C device whose successful transactions are copied into [`I2C_TAP`].
+pub struct Tapped<D> {
+    dev: D,
+}
+
+impl<D> Tapped<D> {
+    pub fn new(dev: D) -> Self {
+        Self { dev }
+    }
+}
+
+impl<D: ErrorType> ErrorType for Tapped<D> {
+    type Error = D::Error;
+}
+
+impl<D: AsyncI2c> AsyncI2c for Tapped<D> {
+    async fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.dev.transaction(address, operations).await?;
+        // As on the wire, with the START / repeated START / STOP marks the
+        // I2C framing draws.
+        I2C_TAP.push_i2c(
+            address,
+            operations.iter().map(|op| match op {
+                Operation::Write(data) => (false, &data[..]),
+                Operation::Read(data) => (true, &data[..]),
+            }),
+        );
+        Ok(())
+    }
+}
@@ -1,7 +1,14 @@
 //! Shared control state, written by the rotary encoder, read by led_strip and
 //! display. Single writer per field, so plain atomic load/store is enough.
 
-use core::sync::atomic::{AtomicBool, AtomicI32, AtomicU32, Ordering::Relaxed};
+use core::sync::atomic::{
+    AtomicBool, AtomicI32, AtomicU32,
+    Ordering::{Acquire, Relaxed, Release},
+};
+
+use led_fx::audio::{Analysis, Audio, BANDS};
+use led_fx::palette::Theme;
+use led_fx::tempo::{self, Tempo};
 
 pub const SPEED_MIN: u32 = 1;
 pub const SPEED_MAX: u32 = 20;
@@ -15,25 +22,66 @@ pub const BRIGHTNESS_MAX: u32 = 8; // brightness level; higher = brighter
 const VEL_MIN_Q8: u32 = 8; // ~0.03 LED/frame
 const VEL_MAX_Q8: u32 = 1024; // 4 LED/frame
 
+// LED supply budget (mA) for all strips together, and the estimated draw the
+// led_strip power limiter reports back.
+pub const LED_BUDGET_MA_DEFAULT: u32 = 1500;
+pub const LED_BUDGET_MA_MIN: u32 = 500;
+pub const LED_BUDGET_MA_MAX: u32 = 4000;
+const LED_BUDGET_MA_STEP: u32 = 250;
+static LED_BUDGET_MA: AtomicU32 = AtomicU32::new(LED_BUDGET_MA_DEFAULT);
+static LED_CURRENT_MA: AtomicU32 = AtomicU32::new(0);
+
+// LED render loop statistics, written by led_strip::run.
+static LED_FRAMES_DROPPED: AtomicU32 = AtomicU32::new(0);
+static LED_RENDER_US: AtomicU32 = AtomicU32::new(0);
+static LED_RENDER_US_MAX: AtomicU32 = AtomicU32::new(0);
+
+// Colour theme of the LED effects (index into `Theme::ALL`).
+static THEME: AtomicU32 = AtomicU32::new(0);
+
+// Effect settings, one slot per `led_strip::setting`. The LED task seeds them
+// with the effects' defaults at start-up; the Effect settings screen edits them.
+pub const EFFECT_PARAMS: usize = 8;
+static EFFECT_PARAM: [AtomicI32; EFFECT_PARAMS] = [const { AtomicI32::new(0) }; EFFECT_PARAMS];
+
+// Tempo clock for beat-synced effects: beat period (ms, 0 = no tempo) and the
+// time (ms) of a downbeat. Tapped in (and nudged) on the Controls screen.
+static TEMPO_BEAT_MS: AtomicU32 = AtomicU32::new(0);
+static TEMPO_ANCHOR_MS: AtomicU32 = AtomicU32::new(0);
+const TEMPO_DEFAULT_BPM: u32 = 120;
+
+// Bus framing the stream strips show (0=raw bits, 1=UART, 2=I2C, 3=SPI).
+pub const FRAMING_COUNT: u32 = 4;
+static FRAMING: AtomicU32 = AtomicU32::new(0);
+
 static SPEED: AtomicU32 = AtomicU32::new(6);
 static VOLUME: AtomicU32 = AtomicU32::new(24); // 80% of VOLUME_MAX (30)
 static BRIGHTNESS: AtomicU32 = AtomicU32::new(5);
 
 // Process running state (all off at boot). Indices for process_running():
 // 0=beer, 1=music, 2=imu, 3=fluids, 4=tilt.
-static BEER_ON: AtomicBool = AtomicBool::new(false);
-// Set by led_strip when the beer byte reaches the end of the strip (= arrives
-// at the servo); consumed by the servo task to start its sequence.
+// BEER shots fired, and the last one whose packet reached the strip end. BEER
+// is on while they differ; the LED task starts the packet afresh on every new
+// shot (one can follow the last before BEER is ever seen off).
+static BEER_SHOT: AtomicU32 = AtomicU32::new(0);
+static BEER_LANDED: AtomicU32 = AtomicU32::new(0);
+// Set by led_strip when the beer packet reaches the end of the strip (= arrives
+// at the servo); consumed by the servo task to send that pour step.
 static BEER_ARRIVED: AtomicBool = AtomicBool::new(false);
 // True while the servo runs the automatic pour sequence (keeps the BEER
 // process indicator lit for the whole pour, not just the LED byte).
 static BEER_POURING: AtomicBool = AtomicBool::new(false);
+// Pour step whose packet the BEER strip carries next (servo::SEQUENCE index).
+static POUR_STEP: AtomicU32 = AtomicU32::new(0);
 static MUSIC_ON: AtomicBool = AtomicBool::new(false);
 static IMU_ON: AtomicBool = AtomicBool::new(false);
 static FLUIDS_ON: AtomicBool = AtomicBool::new(false);
 static TILT_ON: AtomicBool = AtomicBool::new(false);
 static MANUAL_ON: AtomicBool = AtomicBool::new(false);
 static PARTY_ON: AtomicBool = AtomicBool::new(false); // hidden easter egg
+// Idle strips play their ambient playlist (led_strip) instead of staying dark.
+// Off at boot, like the processes: the rig is dark until asked.
+static AMBIENT_ON: AtomicBool = AtomicBool::new(false);
 
 // Manual servo target (0..4095), driven by the encoder in the BEER MANUAL screen.
 pub const SERVO_MIN: u32 = 0;
@@ -54,6 +102,12 @@ static ACCEL_X: AtomicU32 = AtomicU32::new(0);
 static ACCEL_Y: AtomicU32 = AtomicU32::new(0);
 static ACCEL_Z: AtomicU32 = AtomicU32::new(0);
 
+// Analysed music (0..=65535 levels), written by the audio task per block.
+// AUDIO_BEAT_MS is the ms timestamp of the last beat (0 = none yet).
+static AUDIO_BANDS: [AtomicU32; BANDS] = [const { AtomicU32::new(0) }; BANDS];
+static AUDIO_LEVEL: AtomicU32 = AtomicU32::new(0);
+static AUDIO_BEAT_MS: AtomicU32 = AtomicU32::new(0);
+
 pub fn speed() -> u32 {
     SPEED.load(Relaxed)
 }
@@ -72,19 +126,110 @@ pub fn slower() {
     SPEED.store(speed().saturating_sub(1).max(SPEED_MIN), Relaxed);
 }
 
+pub fn framing() -> u32 {
+    FRAMING.load(Relaxed)
+}
+
+pub fn theme() -> Theme {
+    Theme::ALL[THEME.load(Relaxed) as usize]
+}
+
+/// Step to the next (or previous) theme, wrapping around.
+pub fn cycle_theme(up: bool) {
+    let n = Theme::ALL.len() as u32;
+    let t = THEME.load(Relaxed);
+    THEME.store(if up { (t + 1) % n } else { (t + n - 1) % n }, Relaxed);
+}
+
+pub fn tempo() -> Option<Tempo> {
+    match TEMPO_BEAT_MS.load(Acquire) {
+        0 => None,
+        beat_ms => Some(Tempo {
+            beat_ms,
+            anchor_ms: TEMPO_ANCHOR_MS.load(Relaxed),
+        }),
+    }
+}
+
+// Release/Acquire: a reader that sees the new period sees its downbeat.
+pub fn set_tempo(tempo: Option<Tempo>) {
+    match tempo {
+        Some(t) => {
+            TEMPO_ANCHOR_MS.store(t.anchor_ms, Relaxed);
+            TEMPO_BEAT_MS.store(t.beat_ms.max(1), Release);
+        }
+        None => TEMPO_BEAT_MS.store(0, Release),
+    }
+}
+
+/// Step the tempo by 1 BPM, keeping the beat. Stepping down from the slowest
+/// tempo turns the clock off; stepping up from off starts it at the default.
+pub fn nudge_tempo(up: bool, now_ms: u32) {
+    let tempo = match tempo() {
+        Some(t) if !up && t.bpm() <= tempo::BPM_MIN => None,
+        Some(t) if up => Some(t.with_bpm(t.bpm() + 1, now_ms)),
+        Some(t) => Some(t.with_bpm(t.bpm() - 1, now_ms)),
+        None if up => Some(Tempo::from_bpm(TEMPO_DEFAULT_BPM, now_ms)),
+        None => None,
+    };
+    set_tempo(tempo);
+}
+
+pub fn effect_param(index: usize) -> i32 {
+    EFFECT_PARAM[index].load(Relaxed)
+}
+
+pub fn set_effect_param(index: usize, value: i32) {
+    EFFECT_PARAM[index].store(value, Relaxed);
+}
+
+/// Step to the next (or previous) framing, wrapping around.
+pub fn cycle_framing(up: bool) {
+    let f = framing();
+    let n = if up {
+        (f + 1) % FRAMING_COUNT
+    } else {
+        (f + FRAMING_COUNT - 1) % FRAMING_COUNT
+    };
+    FRAMING.store(n, Relaxed);
+}
+
 // --- process state ---------------------------------------------------------
 
 pub fn beer_on() -> bool {
-    BEER_ON.load(Relaxed)
+    beer_shot() != BEER_LANDED.load(Relaxed)
+}
+// Acquire/Release: a shot started by `send_pour_step` must see its step.
+pub fn beer_shot() -> u32 {
+    BEER_SHOT.load(Acquire)
 }
+/// Fire a new BEER shot.
 pub fn start_beer() {
-    BEER_ON.store(true, Relaxed);
+    BEER_SHOT.fetch_add(1, Release);
 }
-pub fn clear_beer() {
-    BEER_ON.store(false, Relaxed);
+
+/// Start a pour from its first step; ignored while one is under way.
+pub fn start_pour() {
+    if !beer_on() && !BEER_POURING.load(Relaxed) {
+        send_pour_step(0);
+    }
 }
 
-/// led_strip signals that the beer byte reached the strip end (the servo).
+/// Fire the BEER shot carrying pour step `step`'s servo packet.
+pub fn send_pour_step(step: u32) {
+    POUR_STEP.store(step, Relaxed);
+    start_beer();
+}
+
+pub fn pour_step() -> u32 {
+    POUR_STEP.load(Relaxed)
+}
+/// Shot `shot`'s packet is done; BEER stays on if a newer one has been fired.
+pub fn clear_beer(shot: u32) {
+    BEER_LANDED.store(shot, Relaxed);
+}
+
+/// led_strip signals that the beer packet reached the strip end (the servo).
 pub fn signal_beer_arrived() {
     BEER_ARRIVED.store(true, Relaxed);
 }
@@ -108,6 +253,13 @@ pub fn set_music(on: bool) {
     MUSIC_ON.store(on, Relaxed);
 }
 
+pub fn ambient_on() -> bool {
+    AMBIENT_ON.load(Relaxed)
+}
+pub fn toggle_ambient() {
+    AMBIENT_ON.store(!ambient_on(), Relaxed);
+}
+
 pub fn party_on() -> bool {
     PARTY_ON.load(Relaxed)
 }
@@ -150,6 +302,9 @@ pub fn imu_ramp_q8(now_ms: u32) -> u32 {
     }
 }
 
+pub fn fluids_on() -> bool {
+    FLUIDS_ON.load(Relaxed)
+}
 pub fn set_fluids_active(on: bool) {
     FLUIDS_ON.store(on, Relaxed);
 }
@@ -185,7 +340,7 @@ pub fn process_running(i: usize) -> bool {
         1 => manual_on(),
         2 => music_on(),
         3 => imu_on(),
-        4 => FLUIDS_ON.load(Relaxed),
+        4 => fluids_on(),
         5 => TILT_ON.load(Relaxed),
         _ => false,
     }
@@ -215,9 +370,46 @@ pub fn dimmer() {
     BRIGHTNESS.store(brightness_level().saturating_sub(1).max(BRIGHTNESS_MIN), Relaxed);
 }
 
-/// Right-shift applied per color channel: level 8 -> 0 (full), level 1 -> 7 (dim).
-pub fn brightness_shift() -> u8 {
-    (BRIGHTNESS_MAX - brightness_level()) as u8
+pub fn led_budget_ma() -> u32 {
+    LED_BUDGET_MA.load(Relaxed)
+}
+/// Raise or lower the LED supply budget by one step (Controls screen).
+pub fn step_led_budget(up: bool) {
+    let ma = if up {
+        led_budget_ma() + LED_BUDGET_MA_STEP
+    } else {
+        led_budget_ma().saturating_sub(LED_BUDGET_MA_STEP)
+    };
+    LED_BUDGET_MA.store(ma.clamp(LED_BUDGET_MA_MIN, LED_BUDGET_MA_MAX), Relaxed);
+}
+
+/// Estimated LED current (mA) across all strips, after power limiting.
+pub fn led_current_ma() -> u32 {
+    LED_CURRENT_MA.load(Relaxed)
+}
+pub fn set_led_current_ma(ma: u32) {
+    LED_CURRENT_MA.store(ma, Relaxed);
+}
+
+/// LED frames skipped because the previous one overran its deadline.
+pub fn led_frames_dropped() -> u32 {
+    LED_FRAMES_DROPPED.load(Relaxed)
+}
+pub fn add_led_frames_dropped(n: u32) {
+    LED_FRAMES_DROPPED.fetch_add(n, Relaxed);
+}
+
+/// Time (us) the last LED frame took to render, all strips together.
+pub fn led_render_us() -> u32 {
+    LED_RENDER_US.load(Relaxed)
+}
+/// Longest LED frame render time (us) since boot.
+pub fn led_render_us_max() -> u32 {
+    LED_RENDER_US_MAX.load(Relaxed)
+}
+pub fn set_led_render_us(us: u32) {
+    LED_RENDER_US.store(us, Relaxed);
+    LED_RENDER_US_MAX.fetch_max(us, Relaxed);
 }
 
 pub fn set_orientation(pitch: i32, roll: i32) {
@@ -250,3 +442,33 @@ pub fn accel_y() -> f32 {
 pub fn accel_z() -> f32 {
     f32::from_bits(ACCEL_Z.load(Relaxed))
 }
+
+pub fn set_audio(analysis: &Analysis, now_ms: u32) {
+    for (band, &level) in AUDIO_BANDS.iter().zip(analysis.bands.iter()) {
+        band.store(level as u32, Relaxed);
+    }
+    AUDIO_LEVEL.store(analysis.level as u32, Relaxed);
+    if analysis.beat {
+        AUDIO_BEAT_MS.store(now_ms.max(1), Relaxed);
+    }
+}
+
+/// Silence the analysed music (the audio task stopped sampling).
+pub fn clear_audio() {
+    set_audio(&Analysis::default(), 0);
+    AUDIO_BEAT_MS.store(0, Relaxed);
+}
+
+/// The music as the effects see it at `now_ms`.
+pub fn audio(now_ms: u32) -> Audio {
+    let beat = AUDIO_BEAT_MS.load(Relaxed);
+    Audio {
+        bands: core::array::from_fn(|b| AUDIO_BANDS[b].load(Relaxed) as u16),
+        level: AUDIO_LEVEL.load(Relaxed) as u16,
+        since_beat_ms: if beat == 0 {
+            u32::MAX
+        } else {
+            now_ms.wrapping_sub(beat)
+        },
+    }
+}
@@ -16,7 +16,10 @@ use oled_async::prelude::*;
 
 use crate::bus::SharedI2c;
 use crate::ui::ViewScreen;
-use crate::{about, control, fluid, nyancat, tilt3d, ui};
+use crate::{about, control, fluid, led_strip, nyancat, tilt3d, ui};
+
+// Rows that fit in the full-height Controls window.
+const CONTROL_ROWS: usize = 5;
 
 struct FmtBuf {
     buf: [u8; 24],
@@ -109,10 +112,13 @@ pub async fn run(i2c: SharedI2c) {
         match view.screen {
             ViewScreen::Controls => {
                 render_main_menu(&mut display, &view, small, med, med_inv, now);
-                let win = Rectangle::new(Point::new(2, 4), Size::new(124, 56));
+                // Full height: five rows, scrolled to keep the cursor in view.
+                let win = Rectangle::new(Point::new(2, 0), Size::new(124, 64));
                 let content = draw_window(&mut display, win, "CONTROLS", small, small_inv);
-                for (i, name) in ui::CONTROL_ITEMS.iter().enumerate() {
-                    let y = content + 2 + i as i32 * 10;
+                let first = view.cursor.saturating_sub(CONTROL_ROWS - 1);
+                let rows = ui::CONTROL_ITEMS.iter().enumerate().skip(first).take(CONTROL_ROWS);
+                for (row, (i, name)) in rows.enumerate() {
+                    let y = content + 1 + row as i32 * 10;
                     let cur = i == view.cursor;
                     let marker = if cur {
                         if view.editing {
@@ -130,6 +136,51 @@ pub async fn run(i2c: SharedI2c) {
                             control::speed(), control::SPEED_MIN, control::SPEED_MAX),
                         2 => row_bar(&mut display, text_style, marker, "Brt", 6, y,
                             control::brightness_level(), control::BRIGHTNESS_MIN, control::BRIGHTNESS_MAX),
+                        3 => {
+                            let mut l = FmtBuf::new();
+                            let _ = write!(l, "{} Frm  {}", marker, ui::FRAMING_NAMES[control::framing() as usize]);
+                            let _ = Text::with_baseline(l.as_str(), Point::new(6, y), text_style, Baseline::Top)
+                                .draw(&mut display);
+                        }
+                        4 => {
+                            let mut l = FmtBuf::new();
+                            let _ = write!(l, "{} Thm  {}", marker, control::theme().name());
+                            let _ = Text::with_baseline(l.as_str(), Point::new(6, y), text_style, Baseline::Top)
+                                .draw(&mut display);
+                        }
+                        5 => {
+                            let mut l = FmtBuf::new();
+                            let on = if control::ambient_on() { "On" } else { "Off" };
+                            let _ = write!(l, "{} Amb  {}", marker, on);
+                            let _ = Text::with_baseline(l.as_str(), Point::new(6, y), text_style, Baseline::Top)
+                                .draw(&mut display);
+                        }
+                        6 => {
+                            let mut l = FmtBuf::new();
+                            let tempo = control::tempo();
+                            let _ = match tempo {
+                                Some(t) => write!(l, "{} Tmp  {} BPM", marker, t.bpm()),
+                                None => write!(l, "{} Tmp  Off", marker),
+                            };
+                            let _ = Text::with_baseline(l.as_str(), Point::new(6, y), text_style, Baseline::Top)
+                                .draw(&mut display);
+                            // Blinks on the beat (bigger on the downbeat) to tap along to.
+                            if let Some(beat) = tempo.map(|t| t.at(now)) {
+                                if beat.phase_q16 < 16384 {
+                                    let size = if beat.is_downbeat() { 7 } else { 5 };
+                                    let _ = Rectangle::new(Point::new(112 - size / 2, y + 4 - size / 2), Size::new(size as u32, size as u32))
+                                        .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+                                        .draw(&mut display);
+                                }
+                            }
+                        }
+                        7 => {
+                            // Estimated draw against the budget it is limited to.
+                            let mut l = FmtBuf::new();
+                            let _ = write!(l, "{} Pwr  {}/{}mA", marker, control::led_current_ma(), control::led_budget_ma());
+                            let _ = Text::with_baseline(l.as_str(), Point::new(6, y), text_style, Baseline::Top)
+                                .draw(&mut display);
+                        }
                         _ => {
                             let mut l = FmtBuf::new();
                             let _ = write!(l, "{} {}", marker, name);
@@ -139,6 +190,33 @@ pub async fn run(i2c: SharedI2c) {
                     }
                 }
             }
+            ViewScreen::Effects => {
+                render_main_menu(&mut display, &view, small, med, med_inv, now);
+                // Generated from the effects' parameter lists, scrolled like
+                // CONTROLS.
+                let win = Rectangle::new(Point::new(2, 0), Size::new(124, 64));
+                let content = draw_window(&mut display, win, "EFFECTS", small, small_inv);
+                let first = view.cursor.saturating_sub(CONTROL_ROWS - 1);
+                for (row, i) in (first..ui::EFFECT_ITEMS).take(CONTROL_ROWS).enumerate() {
+                    let y = content + 1 + row as i32 * 10;
+                    let marker = if i != view.cursor {
+                        " "
+                    } else if view.editing {
+                        "*"
+                    } else {
+                        ">"
+                    };
+                    let mut l = FmtBuf::new();
+                    let _ = match led_strip::setting(i) {
+                        Some((show, param)) => {
+                            write!(l, "{} {} {} {}", marker, show, param.name, control::effect_param(i))
+                        }
+                        None => write!(l, "{} Back", marker),
+                    };
+                    let _ = Text::with_baseline(l.as_str(), Point::new(6, y), text_style, Baseline::Top)
+                        .draw(&mut display);
+                }
+            }
             ViewScreen::Fluids => {
                 render_main_menu(&mut display, &view, small, med, med_inv, now);
                 let win = Rectangle::new(Point::new(4, 4), Size::new(100, 56));
@@ -1,6 +1,7 @@
 //! FLIP fluid animation (fluid_sim crate, MIT, Nicholas-L-Johnson/flip-card)
 //! tilted by the IMU. The `Scene` is tens of KB of fixed arrays, so it lives in
-//! a static -- never the embassy task arena.
+//! a static -- never the embassy task arena. Each step's occupancy grid is also
+//! published for the LED matrix (see [`grid`]).
 
 use core::mem::MaybeUninit;
 use core::sync::atomic::{AtomicU32, Ordering};
@@ -11,8 +12,8 @@ use embedded_graphics::primitives::{PrimitiveStyle, Rectangle};
 use fluid_sim::FluidSimulation::Scene;
 
 const PARTICLES: i32 = 150; // tune for speed vs. fill (vendored grid seeds 150)
-const GRID_W: usize = 24; // get_output() is 24x10 (vendored 26x12 grid - 2)
-const GRID_H: usize = 10;
+pub const GRID_W: usize = 24; // get_output() is 24x10 (vendored 26x12 grid - 2)
+pub const GRID_H: usize = 10;
 const GRAVITY_SCALE: f32 = 20.0; // accel (g) -> sim gravity (>9.81 = livelier)
 const FLIP_RATIO: f32 = 0.90; // FLIP/PIC blend = viscosity: lower = more viscous
 const MAX_SUBSTEPS: u32 = 6; // clamp so a slow frame can't death-spiral
@@ -22,6 +23,9 @@ const MAX_SUBSTEPS: u32 = 6; // clamp so a slow frame can't death-spiral
 // construction -- that ~50KB stack peak is what crashed FLUIDS at high res.
 static mut SCENE: MaybeUninit<Scene> = MaybeUninit::uninit();
 static LAST_MS: AtomicU32 = AtomicU32::new(0);
+// The latest occupancy grid, one bitmask per row (bit x = cell x full), for
+// the LED task on the other core.
+static GRID: [AtomicU32; GRID_H] = [const { AtomicU32::new(0) }; GRID_H];
 
 pub fn init() -> &'static mut Scene {
     // SAFETY: called once at startup; the returned &'static mut is used only by
@@ -58,6 +62,10 @@ where
     }
 
     let grid = scene.get_output(); // [y][x] occupancy
+    for (row, cells) in GRID.iter().zip(grid.iter()) {
+        let bits = cells.iter().enumerate().fold(0, |m, (x, &on)| m | ((on as u32) << x));
+        row.store(bits, Ordering::Relaxed);
+    }
     let cell = (w / GRID_W as i32).min(h / GRID_H as i32).max(1);
     let x_off = ox + (w - GRID_W as i32 * cell) / 2;
     let y_off = oy + (h - GRID_H as i32 * cell) / 2;
@@ -74,3 +82,9 @@ where
         }
     }
 }
+
+/// The latest occupancy grid: `GRID_H` rows, top first, bit x of each set
+/// where cell x holds fluid. Rows may be from consecutive steps.
+pub fn grid() -> [u32; GRID_H] {
+    core::array::from_fn(|y| GRID[y].load(Ordering::Relaxed))
+}
@@ -1,13 +1,14 @@
 //! MPU6050 IMU on the shared I2C bus. Publishes accel-derived orientation
-//! (pitch/roll) to `control` for the display. Address 0x68.
+//! (pitch/roll) to `control` for the display. Address 0x68. Its bus traffic
+//! is tapped for the IMU LED stream.
 
 use embassy_time::{Duration, Timer};
 
-use crate::bus::SharedI2c;
+use crate::bus::TappedI2c;
 use crate::control;
 
 #[embassy_executor::task]
-pub async fn run(i2c: SharedI2c) {
+pub async fn run(i2c: TappedI2c) {
     let mut mpu = edrv_mpu6050::MPU6050::new(i2c, 0x68);
 
     // Let the MPU power rail settle before the first transaction.
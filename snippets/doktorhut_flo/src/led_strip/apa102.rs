@@ -0,0 +1,135 @@
+//! APA102 / SK9822 (clocked, SPI) LED driver, behind the same `Framebuffer` API
+//! as the one-wire [`Ws2812`](super::Ws2812). No bit timing to meet, so frames
+//! just go out over async SPI (mode 0, SCK -> CI, MOSI -> DI, up to ~10 MHz).
+//!
+//! Frame: 4 zero bytes, then per LED `0xE0 | global` (5 bit), B, G, R, then
+//! zero bytes to clock the data through the chain (one clock per 2 LEDs; the
+//! SK9822 also latches on them). The 5-bit global current is picked per LED as
+//! the smallest that still reaches its brightest channel, which buys extra
+//! precision at the dim end.
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use esp_hal::spi::master::Spi;
+use esp_hal::Async;
+
+use super::color::{brightness_q16, duty};
+use super::{power, Framebuffer, MAX_LEDS};
+use crate::control;
+
+const START: usize = 4;
+const BUF_LEN: usize = START + MAX_LEDS * 4 + end_len(MAX_LEDS);
+
+// Frame buffers live in static memory, not the embassy task arena. The ESP32
+// has two general-purpose SPI masters (SPI2/SPI3).
+const MAX_STRIPS: usize = 2;
+static mut BUFS: [[u8; BUF_LEN]; MAX_STRIPS] = [[0; BUF_LEN]; MAX_STRIPS];
+static NEXT_BUF: AtomicUsize = AtomicUsize::new(0);
+// Power slots after the RMT channels.
+const POWER_SLOT_BASE: usize = 8;
+
+// SPI clock assumed for frame timing unless told otherwise (`with_clock_khz`).
+const CLOCK_KHZ_DEFAULT: u32 = 4000;
+
+const fn end_len(leds: usize) -> usize {
+    4 + leds.div_ceil(16)
+}
+
+pub struct Apa102 {
+    spi: Spi<'static, Async>,
+    slot: usize,
+    len: usize,
+    buf: &'static mut [u8; BUF_LEN],
+    brightness_cap: u32,
+    clock_khz: u32,
+}
+
+impl Apa102 {
+    /// A strip of `len` LEDs (at most [`MAX_LEDS`]) on an async SPI master.
+    pub fn new(spi: Spi<'static, Async>, len: usize) -> Self {
+        assert!(len <= MAX_LEDS, "strip longer than MAX_LEDS");
+        let idx = NEXT_BUF.fetch_add(1, Ordering::Relaxed);
+        assert!(idx < MAX_STRIPS, "more APA102 strips than MAX_STRIPS");
+        // SAFETY: each idx is handed out exactly once, so this &mut is unique.
+        let buf = unsafe { &mut *(&raw mut BUFS[idx]) };
+        Self {
+            spi,
+            slot: POWER_SLOT_BASE + idx,
+            len,
+            buf,
+            brightness_cap: control::BRIGHTNESS_MAX,
+            clock_khz: CLOCK_KHZ_DEFAULT,
+        }
+    }
+
+    /// The SPI clock the bus was configured with, for [`frame_us`](Self::frame_us).
+    pub fn with_clock_khz(mut self, khz: u32) -> Self {
+        self.clock_khz = khz.max(1);
+        self
+    }
+
+    /// Clamp the global brightness level to `level` for this strip.
+    pub fn set_brightness_cap(&mut self, level: u32) {
+        self.brightness_cap = level;
+    }
+
+    /// Number of LEDs on this strip.
+    pub fn num_leds(&self) -> usize {
+        self.len
+    }
+
+    /// Time (us) a frame takes on the wire.
+    pub fn frame_us(&self) -> u32 {
+        let bytes = (START + self.len * 4 + end_len(self.len)) as u32;
+        (bytes * 8 * 1000).div_ceil(self.clock_khz)
+    }
+
+    /// Encode `fb` and send it; resolves once the SPI transfer is done.
+    pub async fn write(&mut self, fb: &Framebuffer) {
+        self.begin_write(fb);
+        self.end_write().await;
+    }
+
+    /// Encode `fb` (and claim its power budget); sent by
+    /// [`end_write`](Self::end_write).
+    pub fn begin_write(&mut self, fb: &Framebuffer) {
+        let leds = &fb[..fb.len().min(self.len)];
+        let mut bright = brightness_q16(control::brightness_level().min(self.brightness_cap));
+
+        let demand: u32 = leds
+            .iter()
+            .map(|c| c.iter().map(|&v| duty(v, bright)).sum::<u32>() / 257)
+            .sum();
+        let scale_q16 = power::request(
+            self.slot,
+            power::estimate_ma(demand, self.len),
+            power::idle_ma(self.len),
+        );
+        bright = ((bright as u64 * scale_q16 as u64) >> 16) as u32;
+
+        let mut drawn = 0;
+        let body = &mut self.buf[START..START + self.len * 4];
+        for (out, &[r, g, b]) in body.chunks_exact_mut(4).zip(leds.iter()) {
+            let d = [b, g, r].map(|v| duty(v, bright)); // 16-bit duties
+            let peak = d[0].max(d[1]).max(d[2]);
+            let global = peak.div_ceil(65535 / 31).clamp(1, 31);
+            out[0] = 0xE0 | global as u8;
+            for (o, &v) in out[1..].iter_mut().zip(d.iter()) {
+                let c = ((v * 31 / global + 128) / 257).min(255);
+                *o = c as u8;
+                drawn += c * global / 31;
+            }
+        }
+        power::report(self.slot, power::estimate_ma(drawn, self.len));
+    }
+
+    /// Send the frame encoded by [`begin_write`](Self::begin_write).
+    pub async fn end_write(&mut self) {
+        // Start and end frames are zeros and never written (the LED slots past
+        // `len` stay zero too).
+        let n = START + self.len * 4 + end_len(self.len);
+        if let Err(e) = self.spi.write_async(&self.buf[..n]).await {
+            log::warn!("apa102 spi write failed: {e:?}");
+        }
+    }
+}
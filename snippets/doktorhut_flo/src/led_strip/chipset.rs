@@ -0,0 +1,97 @@
+//! Addressable LED chipsets: one-wire bit timings (for the RMT driver) and the
+//! order the colour channels go out in.
+
+use super::Rgb;
+
+/// One-wire bit timing in RMT ticks (80 MHz, divider 1 -> 12.5 ns/tick).
+#[derive(Clone, Copy)]
+pub struct Timing {
+    pub t0h: u16,
+    pub t0l: u16,
+    pub t1h: u16,
+    pub t1l: u16,
+}
+
+/// Channel order on the wire. `Grbw` is a 4-channel RGBW part; its white
+/// channel is extracted from the RGB framebuffer.
+#[derive(Clone, Copy, PartialEq)]
+pub enum ColorOrder {
+    Rgb,
+    Grb,
+    Brg,
+    Grbw,
+}
+
+impl ColorOrder {
+    /// Bytes per LED on the wire.
+    pub const fn channels(self) -> usize {
+        match self {
+            ColorOrder::Grbw => 4,
+            _ => 3,
+        }
+    }
+
+    /// Reorder `c` into wire order; the first `channels()` entries are used.
+    /// For RGBW the common part of R/G/B moves to the white LED (same light,
+    /// whiter and less current).
+    pub fn arrange(self, c: Rgb) -> [u16; 4] {
+        let [r, g, b] = c;
+        match self {
+            ColorOrder::Rgb => [r, g, b, 0],
+            ColorOrder::Grb => [g, r, b, 0],
+            ColorOrder::Brg => [b, r, g, 0],
+            ColorOrder::Grbw => {
+                let w = r.min(g).min(b);
+                [g - w, r - w, b - w, w]
+            }
+        }
+    }
+}
+
+/// A one-wire chipset: timing plus native colour order.
+#[derive(Clone, Copy)]
+pub struct Chipset {
+    pub timing: Timing,
+    pub order: ColorOrder,
+}
+
+impl Chipset {
+    /// WS2812B: 0.40/0.85 us (0), 0.80/0.45 us (1), +-150 ns. GRB.
+    pub const WS2812B: Chipset = Chipset {
+        timing: Timing {
+            t0h: 32,
+            t0l: 68,
+            t1h: 64,
+            t1l: 36,
+        },
+        order: ColorOrder::Grb,
+    };
+
+    /// WS2811 (800 kHz mode): 0.25/1.00 us (0), 0.60/0.65 us (1). Usually RGB.
+    pub const WS2811: Chipset = Chipset {
+        timing: Timing {
+            t0h: 20,
+            t0l: 80,
+            t1h: 48,
+            t1l: 52,
+        },
+        order: ColorOrder::Rgb,
+    };
+
+    /// SK6812 RGBW: 0.30/0.90 us (0), 0.60/0.60 us (1). GRBW.
+    pub const SK6812_RGBW: Chipset = Chipset {
+        timing: Timing {
+            t0h: 24,
+            t0l: 72,
+            t1h: 48,
+            t1l: 48,
+        },
+        order: ColorOrder::Grbw,
+    };
+
+    /// Same timing, different colour order (strips of one chip vary).
+    pub const fn with_order(mut self, order: ColorOrder) -> Self {
+        self.order = order;
+        self
+    }
+}
@@ -0,0 +1,70 @@
+//! Per-strip configuration: what a strip shows and how it is mounted, so a
+//! strip wired backwards, with a different colour order or a brighter LED batch
+//! needs only a different record in `main`, not code changes.
+
+use super::matrix::XyMap;
+use super::transition::Transition;
+use super::{ColorOrder, StripRole};
+use crate::control::BRIGHTNESS_MAX;
+
+#[derive(Clone, Copy)]
+pub struct StripConfig {
+    pub role: StripRole,
+    /// Number of LEDs (at most `MAX_LEDS`); the driver is created with it.
+    pub len: usize,
+    /// Overrides the chipset's native colour order (one-wire strips only).
+    pub order: Option<ColorOrder>,
+    /// Data-in at the far end: index 0 of the rendered frame goes out last.
+    pub reverse: bool,
+    /// Highest `control` brightness level this strip follows (1..=8); the
+    /// global level is clamped to it.
+    pub brightness_cap: u32,
+    /// How the strip changes over when what it shows changes.
+    pub transition: Transition,
+    /// A matrix panel's layout (None for a plain strip); 2D effects draw
+    /// through it.
+    pub matrix: Option<XyMap>,
+}
+
+const DEFAULT_TRANSITION: Transition = Transition::crossfade(400);
+
+impl StripConfig {
+    pub const fn new(role: StripRole, len: usize) -> Self {
+        Self {
+            role,
+            len,
+            order: None,
+            reverse: false,
+            brightness_cap: BRIGHTNESS_MAX,
+            transition: DEFAULT_TRANSITION,
+            matrix: None,
+        }
+    }
+
+    /// A matrix panel wired as `map`; its length is the map's.
+    pub const fn with_matrix(mut self, map: XyMap) -> Self {
+        self.len = map.len();
+        self.matrix = Some(map);
+        self
+    }
+
+    pub const fn with_order(mut self, order: ColorOrder) -> Self {
+        self.order = Some(order);
+        self
+    }
+
+    pub const fn reversed(mut self) -> Self {
+        self.reverse = true;
+        self
+    }
+
+    pub const fn with_brightness_cap(mut self, level: u32) -> Self {
+        self.brightness_cap = level;
+        self
+    }
+
+    pub const fn with_transition(mut self, transition: Transition) -> Self {
+        self.transition = transition;
+        self
+    }
+}
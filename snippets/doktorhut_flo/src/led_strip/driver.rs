@@ -1,65 +1,132 @@
-//! WS2812B driver over RMT (blocking, streaming — not limited by channel RAM).
+//! One-wire LED driver (WS2812B, WS2811, SK6812 RGBW, see [`Chipset`]) over RMT
+//! with a streaming (ping-pong) encoder.
+//!
+//! Per strip only the wire bytes (3 or 4 bytes/LED, runtime length) are kept.
+//! The RMT interrupt expands them into pulse codes half a channel RAM at a time:
+//! while the transmitter drains one half, the ISR refills the other from the
+//! bytes. The task awaits the end-of-frame interrupt, so the core is free while
+//! bits go out and the strips overlap.
 //!
 //! `Rmt` is created once via [`new_rmt`]; each strip then takes a different RMT
 //! channel. Channels must be spaced by `memsize` (no block overlap). With
 //! `MEMSIZE = 2`: channels 0/2/4/6 (blocks 0-1, 2-3, 4-5, 6-7) -> up to 4
 //! strips. The ESP32 has 8 RMT blocks total.
 
-use core::sync::atomic::{AtomicUsize, Ordering};
+use core::cell::RefCell;
+use core::future::poll_fn;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use core::task::Poll;
 
+use critical_section::Mutex;
+use embassy_sync::waitqueue::AtomicWaker;
 use esp_hal::gpio::interconnect::PeripheralOutput;
 use esp_hal::gpio::Level;
+use esp_hal::handler;
 use esp_hal::peripherals::RMT;
 use esp_hal::rmt::{
-    AnyTxChannel, PulseCode, RawChannelAccess, Rmt, Tx, TxChannel, TxChannelConfig,
-    TxChannelCreator,
+    AnyTxChannel, ChannelCreator, PulseCode, Rmt, TxChannelConfig, TxChannelCreator,
 };
 use esp_hal::time::Rate;
 use esp_hal::Blocking;
 
-use super::{Framebuffer, NUM_LEDS};
+use super::chipset::{Chipset, ColorOrder};
+use super::color::{brightness_q16, duty, encode_channel, encode_channel_dithered};
+use super::{power, Framebuffer, MAX_LEDS};
 use crate::control;
 
-// RMT at 80 MHz, divider 1 -> 12.5 ns/tick. WS2812B timing (+-150 ns):
-const T0H: u16 = 32; // 0.40 us
-const T0L: u16 = 68; // 0.85 us
-const T1H: u16 = 64; // 0.80 us
-const T1L: u16 = 36; // 0.45 us
-
-const BUF_LEN: usize = NUM_LEDS * 24 + 1;
+// Room for 4-channel (RGBW) LEDs.
+const WIRE_LEN: usize = MAX_LEDS * 4;
 
-// Pulse buffers live in static memory, not the embassy task arena: each one is
-// ~`NUM_LEDS * 96` bytes and would otherwise blow the arena. Bump MAX_STRIPS to
-// drive more strips.
-const MAX_STRIPS: usize = 3;
+// Wire (and dither residual) buffers live in static memory, not the embassy
+// task arena. Bump MAX_STRIPS to drive more strips (at most 8 / MEMSIZE).
+const MAX_STRIPS: usize = 4;
 const MEMSIZE: u8 = 2;
-static mut PULSE_BUFS: [[u32; BUF_LEN]; MAX_STRIPS] = [[0; BUF_LEN]; MAX_STRIPS];
+static mut WIRE_BUFS: [[u8; WIRE_LEN]; MAX_STRIPS] = [[0; WIRE_LEN]; MAX_STRIPS];
+static mut DITHER_BUFS: [[u8; WIRE_LEN]; MAX_STRIPS] = [[0; WIRE_LEN]; MAX_STRIPS];
 static NEXT_BUF: AtomicUsize = AtomicUsize::new(0);
 
-fn claim_buf() -> &'static mut [u32; BUF_LEN] {
+// ESP32 RMT channel RAM: 8 blocks of 64 codes; channel n starts at block n.
+const RMT_RAM: *mut u32 = 0x3FF5_6800 as *mut u32;
+const BLOCK_CODES: usize = 64;
+const CHANNELS: usize = 8;
+// Codes per ping-pong half (= the TX threshold); 8 codes per wire byte.
+const HALF: usize = MEMSIZE as usize * BLOCK_CODES / 2;
+// RMT ticks per microsecond (80 MHz, divider 1), and the low time that
+// latches a frame (WS2812B v5 and SK6812 want > 280 us, the older parts 50).
+const TICKS_PER_US: u32 = 80;
+const LATCH_US: u32 = 300;
+
+/// What the ISR is currently streaming on a channel.
+struct TxJob {
+    data: *const u8,
+    len: usize,
+    one: u32,    // pulse code for a 1 bit
+    zero: u32,   // pulse code for a 0 bit
+    bit: usize,  // next bit of `data` to encode
+    half: usize, // RAM half (0/1) the next refill goes to
+    ended: bool, // end marker written
+}
+
+// SAFETY: `data` points into a `'static` wire buffer that its `Ws2812` does not
+// touch until the job completes (write() awaits the end interrupt).
+unsafe impl Send for TxJob {}
+
+static JOBS: Mutex<RefCell<[Option<TxJob>; CHANNELS]>> =
+    Mutex::new(RefCell::new([const { None }; CHANNELS]));
+static DONE: [AtomicBool; CHANNELS] = [const { AtomicBool::new(false) }; CHANNELS];
+static WAKERS: [AtomicWaker; CHANNELS] = [const { AtomicWaker::new() }; CHANNELS];
+
+/// Claim one strip's (wire, dither residual) buffers.
+fn claim_bufs() -> (&'static mut [u8; WIRE_LEN], &'static mut [u8; WIRE_LEN]) {
     let idx = NEXT_BUF.fetch_add(1, Ordering::Relaxed);
     assert!(idx < MAX_STRIPS, "more strips than MAX_STRIPS");
-    // SAFETY: each idx is handed out exactly once, so this &mut is unique.
-    unsafe { &mut *(&raw mut PULSE_BUFS[idx]) }
+    // SAFETY: each idx is handed out exactly once, so these &muts are unique.
+    unsafe {
+        (
+            &mut *(&raw mut WIRE_BUFS[idx]),
+            &mut *(&raw mut DITHER_BUFS[idx]),
+        )
+    }
 }
 
-/// Create the shared RMT peripheral at the 80 MHz the timing constants assume.
+/// Create the shared RMT peripheral at the 80 MHz the timing constants assume,
+/// with the streaming ISR installed (on the calling core).
 pub fn new_rmt(rmt: RMT<'static>) -> Rmt<'static, Blocking> {
-    Rmt::new(rmt, Rate::from_mhz(80)).unwrap()
+    let mut rmt = Rmt::new(rmt, Rate::from_mhz(80)).unwrap();
+    rmt.set_interrupt_handler(rmt_isr);
+    // Wrap mode: after the last code in its RAM the transmitter continues from
+    // the first one, which is what makes ping-pong refills possible. (On the
+    // ESP32 this is a single switch for all channels.)
+    RMT::regs()
+        .apb_conf()
+        .modify(|_, w| w.mem_tx_wrap_en().set_bit());
+    rmt
 }
 
 pub struct Ws2812 {
-    // Option: transmit() consumes the channel, wait() hands it back.
-    channel: Option<AnyTxChannel<Blocking>>,
-    pulse: &'static mut [u32; BUF_LEN],
+    // Kept so the channel (and its pin routing) stays claimed; the ISR drives
+    // the hardware directly by channel index.
+    _channel: AnyTxChannel<Blocking>,
+    ch: u8,
+    len: usize,
+    chipset: Chipset,
+    wire: &'static mut [u8; WIRE_LEN],
+    // Per-channel rounding remainder carried to the next frame (temporal
+    // dithering, only used while `dither` is set).
+    residual: &'static mut [u8; WIRE_LEN],
+    dither: bool,
+    brightness_cap: u32,
 }
 
 impl Ws2812 {
-    pub fn new<'d, C>(creator: C, data: impl PeripheralOutput<'d>) -> Self
-    where
-        C: TxChannelCreator<'d, Blocking>,
-        C::Raw: RawChannelAccess<Dir = Tx>,
-    {
+    /// Claim RMT channel `CH` for a WS2812B strip of `len` LEDs (at most
+    /// [`MAX_LEDS`]); see [`Ws2812::with_chipset`] for other parts.
+    pub fn new<const CH: u8>(
+        creator: ChannelCreator<Blocking, CH>,
+        data: impl PeripheralOutput<'static>,
+        len: usize,
+    ) -> Self {
+        assert!(len <= MAX_LEDS, "strip longer than MAX_LEDS");
         let channel = creator
             .configure_tx(
                 data,
@@ -72,42 +139,249 @@ impl Ws2812 {
             .unwrap()
             .degrade();
 
+        let (wire, residual) = claim_bufs();
         Self {
-            channel: Some(channel),
-            pulse: claim_buf(),
+            _channel: channel,
+            ch: CH,
+            len,
+            chipset: Chipset::WS2812B,
+            wire,
+            residual,
+            dither: false,
+            brightness_cap: control::BRIGHTNESS_MAX,
         }
     }
 
-    pub fn write(&mut self, fb: &Framebuffer) {
-        encode(self.pulse, fb);
+    /// Drive a different one-wire chipset (timing and colour order).
+    pub fn with_chipset(mut self, chipset: Chipset) -> Self {
+        self.chipset = chipset;
+        self
+    }
+
+    /// Enable frame-to-frame error-diffusion dithering. Keeps fractional
+    /// values of dim colours and slow fades (visible as a fine shimmer at the
+    /// 8 ms frame rate instead of hard steps).
+    pub fn with_dither(mut self, on: bool) -> Self {
+        self.residual.fill(0);
+        self.dither = on;
+        self
+    }
 
-        let ch = self.channel.take().expect("rmt channel present");
-        let tx = ch.transmit(&self.pulse[..]).expect("rmt transmit");
-        self.channel = Some(match tx.wait() {
-            Ok(ch) => ch,
-            Err((_e, ch)) => ch,
-        });
+    /// Override the chipset's colour order (e.g. an RGB batch of WS2812s).
+    pub fn set_color_order(&mut self, order: ColorOrder) {
+        self.chipset = self.chipset.with_order(order);
+    }
+
+    /// Clamp the global brightness level to `level` for this strip.
+    pub fn set_brightness_cap(&mut self, level: u32) {
+        self.brightness_cap = level;
+    }
+
+    /// Number of LEDs on this strip.
+    pub fn num_leds(&self) -> usize {
+        self.len
+    }
+
+    /// Time (us) a frame takes on the wire, latch included.
+    pub fn frame_us(&self) -> u32 {
+        let t = self.chipset.timing;
+        let bit_ticks = (t.t0h + t.t0l).max(t.t1h + t.t1l) as u32;
+        let bits = (self.len * self.chipset.order.channels() * 8) as u32;
+        bits * bit_ticks / TICKS_PER_US + LATCH_US
+    }
+
+    /// Encode `fb` and send it. Resolves once the whole frame is on the wire;
+    /// other tasks (and the other strips) run in the meantime.
+    pub async fn write(&mut self, fb: &Framebuffer) {
+        self.begin_write(fb);
+        self.end_write().await;
+    }
+
+    /// Encode `fb` and start sending it; the RMT interrupt streams it out.
+    pub fn begin_write(&mut self, fb: &Framebuffer) {
+        let n = self.len * self.chipset.order.channels();
+        let wire = &mut self.wire[..n];
+        let residual = self.dither.then_some(&mut self.residual[..n]);
+        let mut bright = brightness_q16(control::brightness_level().min(self.brightness_cap));
+
+        // The power limit goes into the brightness, so the dither residual
+        // carries what is actually sent (scaling the encoded bytes afterwards
+        // would leave the unlimited level's error for the next frame).
+        let scale_q16 = power::request(
+            self.ch as usize,
+            power::estimate_ma(demand(wire, fb, self.chipset, bright), self.len),
+            power::idle_ma(self.len),
+        );
+        bright = ((bright as u64 * scale_q16 as u64) >> 16) as u32;
+        encode(wire, residual, fb, self.chipset, bright);
+        let drawn = wire.iter().map(|&b| b as u32).sum();
+        power::report(self.ch as usize, power::estimate_ma(drawn, self.len));
+
+        DONE[self.ch as usize].store(false, Ordering::Relaxed);
+        start(self.ch, wire, self.chipset);
+    }
+
+    /// Resolves once the frame from [`begin_write`](Self::begin_write) is out.
+    pub async fn end_write(&mut self) {
+        let ch = self.ch as usize;
+        poll_fn(|cx| {
+            WAKERS[ch].register(cx.waker());
+            if DONE[ch].load(Ordering::Acquire) {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await;
     }
 }
 
-// GRB order, MSB first, with the current (runtime) brightness shift applied.
-fn encode(buf: &mut [u32; BUF_LEN], fb: &Framebuffer) {
-    let shift = control::brightness_shift();
-    let mut i = 0;
-    for &[r, g, b] in fb.iter() {
-        let r = r >> shift;
-        let g = g >> shift;
-        let b = b >> shift;
-        for byte in [g, r, b] {
-            for bit in (0..8).rev() {
-                buf[i] = if (byte >> bit) & 1 == 1 {
-                    PulseCode::new(Level::High, T1H, Level::Low, T1L)
-                } else {
-                    PulseCode::new(Level::High, T0H, Level::Low, T0L)
-                };
-                i += 1;
+// Sum of the 8-bit duties `fb` encodes to at `bright`, plus the tail LEDs
+// `encode` leaves at their previous bytes.
+fn demand(wire: &[u8], fb: &Framebuffer, chipset: Chipset, bright: u32) -> u32 {
+    let n = chipset.order.channels();
+    let leds = fb.len().min(wire.len() / n);
+    let lit: u32 = fb[..leds]
+        .iter()
+        .map(|&c| {
+            chipset.order.arrange(c)[..n]
+                .iter()
+                .map(|&v| duty(v, bright))
+                .sum::<u32>()
+                / 257
+        })
+        .sum();
+    lit + wire[leds * n..].iter().map(|&b| b as u32).sum::<u32>()
+}
+
+// Chipset colour order, MSB first, with gamma and the brightness scale
+// `bright` (Q16) applied per channel, dithered if `residual` is given. A
+// shorter `fb` leaves the tail LEDs at their previous value.
+fn encode(
+    wire: &mut [u8],
+    residual: Option<&mut [u8]>,
+    fb: &Framebuffer,
+    chipset: Chipset,
+    bright: u32,
+) {
+    let n = chipset.order.channels();
+    match residual {
+        Some(residual) => {
+            let px = wire.chunks_exact_mut(n).zip(residual.chunks_exact_mut(n));
+            for ((out, err), &c) in px.zip(fb.iter()) {
+                let vals = chipset.order.arrange(c);
+                for ((o, e), &v) in out.iter_mut().zip(err.iter_mut()).zip(vals.iter()) {
+                    *o = encode_channel_dithered(v, bright, e);
+                }
+            }
+        }
+        None => {
+            for (out, &c) in wire.chunks_exact_mut(n).zip(fb.iter()) {
+                let vals = chipset.order.arrange(c);
+                for (o, &v) in out.iter_mut().zip(vals.iter()) {
+                    *o = encode_channel(v, bright);
+                }
             }
         }
     }
-    buf[i] = PulseCode::empty();
+}
+
+/// Prefill both RAM halves, then kick off the transmitter on channel `ch`.
+fn start(ch: u8, wire: &[u8], chipset: Chipset) {
+    let t = chipset.timing;
+    let rmt = RMT::regs();
+    critical_section::with(|cs| {
+        let mut jobs = JOBS.borrow_ref_mut(cs);
+        let job = jobs[ch as usize].insert(TxJob {
+            data: wire.as_ptr(),
+            len: wire.len(),
+            one: PulseCode::new(Level::High, t.t1h, Level::Low, t.t1l),
+            zero: PulseCode::new(Level::High, t.t0h, Level::Low, t.t0l),
+            bit: 0,
+            half: 0,
+            ended: false,
+        });
+        refill(ch, job);
+        refill(ch, job);
+
+        rmt.ch_tx_lim(ch as usize)
+            .modify(|_, w| unsafe { w.tx_lim().bits(HALF as u16) });
+        rmt.int_clr().write(|w| {
+            w.ch_tx_thr_event(ch).set_bit();
+            w.ch_tx_end(ch).set_bit();
+            w.ch_err(ch).set_bit()
+        });
+        rmt.int_ena().modify(|_, w| {
+            w.ch_tx_thr_event(ch).set_bit();
+            w.ch_tx_end(ch).set_bit();
+            w.ch_err(ch).set_bit()
+        });
+        rmt.chconf1(ch as usize).modify(|_, w| {
+            w.mem_owner().clear_bit(); // transmitter owns the RAM
+            w.mem_rd_rst().set_bit();
+            w.apb_mem_rst().set_bit();
+            w.tx_start().set_bit()
+        });
+    });
+}
+
+/// Encode the next `HALF` codes of `job` into its next RAM half. Past the last
+/// bit, writes the end marker (a zero-length code) and zero padding.
+fn refill(ch: u8, job: &mut TxJob) {
+    let total_bits = job.len * 8;
+    let base = ch as usize * BLOCK_CODES + job.half * HALF;
+    for i in 0..HALF {
+        let code = if job.bit < total_bits {
+            // SAFETY: bit / 8 < len, inside the job's wire buffer.
+            let byte = unsafe { *job.data.add(job.bit / 8) };
+            let one = (byte >> (7 - job.bit % 8)) & 1 == 1;
+            job.bit += 1;
+            if one {
+                job.one
+            } else {
+                job.zero
+            }
+        } else {
+            job.ended = true;
+            PulseCode::empty()
+        };
+        // SAFETY: `base + i` stays within this channel's MEMSIZE blocks; the
+        // transmitter is reading the other half. RMT RAM needs 32-bit writes.
+        unsafe { RMT_RAM.add(base + i).write_volatile(code) };
+    }
+    job.half ^= 1;
+}
+
+#[handler]
+fn rmt_isr() {
+    let rmt = RMT::regs();
+    let st = rmt.int_st().read();
+    for ch in (0..CHANNELS as u8).step_by(MEMSIZE as usize) {
+        if st.ch_tx_thr_event(ch).bit_is_set() {
+            rmt.int_clr().write(|w| w.ch_tx_thr_event(ch).set_bit());
+            critical_section::with(|cs| {
+                if let Some(job) = JOBS.borrow_ref_mut(cs)[ch as usize].as_mut() {
+                    // The half just drained gets the next codes; once the end
+                    // marker is out there is nothing left to stream.
+                    if !job.ended {
+                        refill(ch, job);
+                    }
+                }
+            });
+        }
+        if st.ch_tx_end(ch).bit_is_set() || st.ch_err(ch).bit_is_set() {
+            rmt.int_ena().modify(|_, w| {
+                w.ch_tx_thr_event(ch).clear_bit();
+                w.ch_tx_end(ch).clear_bit();
+                w.ch_err(ch).clear_bit()
+            });
+            rmt.int_clr().write(|w| {
+                w.ch_tx_end(ch).set_bit();
+                w.ch_err(ch).set_bit()
+            });
+            critical_section::with(|cs| JOBS.borrow_ref_mut(cs)[ch as usize] = None);
+            DONE[ch as usize].store(true, Ordering::Release);
+            WAKERS[ch as usize].wake();
+        }
+    }
 }
@@ -1,31 +1,61 @@
-//! WS2812B LED strip: visualize bytes travelling down a wire.
+//! Addressable LED strips: visualize bytes travelling down a wire.
 
+mod apa102;
+pub mod chipset;
+mod config;
 mod driver;
-pub mod effects;
+pub mod power;
+
+// Pixels, colour, effects and compositing build for the host too (simulator,
+// tests); see `led_fx`.
+pub use led_fx::{
+    color, compose, effects, matrix, palette, playlist, transition, Framebuffer, Rgb, MAX_LEDS,
+};
 
 use embassy_time::{Duration, Instant, Timer};
-use libm::{fabsf, sqrtf};
 
-use crate::control;
+use crate::{bus, control, fluid, servo};
+pub use apa102::Apa102;
+pub use chipset::{Chipset, ColorOrder};
+use compose::{Compositor, Layer, Mask};
+pub use config::StripConfig;
 pub use driver::{new_rmt, Ws2812};
-use effects::{BeerByte, Effect, Stream};
-
-/// Compile-time strip length. The pulse buffer (`NUM_LEDS * 24 + 1` u32) lives
-/// in the task arena; the default 20480 holds up to ~100 LEDs.
-pub const NUM_LEDS: usize = 60;
-
-/// RGB pixel; driver reorders to WS2812B GRB.
-pub type Rgb = [u8; 3];
-pub type Framebuffer = [Rgb; NUM_LEDS];
+use effects::source::{Gaps, TapReader};
+use effects::framing::{I2c, Spi, Uart};
+use effects::{
+    BeerByte, Chase, Comet, Ctx, Effect, Fire, Framed, Larson, Occupancy, Param, Party, Plasma,
+    Plasma2d, Spectrum, Stream, Twinkle,
+};
+use matrix::{Layout, XyMap};
+use palette::PaletteFade;
+use playlist::{Entry, Playlist};
+use transition::{Fade, Transition};
 
-const FRAME_MS: u64 = 8;
+// Shortest frame period; longer strips stretch it (see `frame_period`).
+const FRAME_MS: u32 = 8;
+// Render time allowed for on top of the longest strip's transmission.
+const RENDER_US: u32 = 2000;
+// Longest tick handed to effects (a stalled frame must not teleport them).
+const MAX_DT_MS: u32 = 100;
+// A new colour theme blends in over this long.
+const THEME_FADE: Transition = Transition::crossfade(1500);
 
-const BEER_BYTE: u8 = 0b1011_0010;
-// Beer byte travels at this fraction of the configured LED speed (slower than
+// Beer packet travels at this fraction of the configured LED speed (slower than
 // the stream, but still scales with it -> servo triggers earlier when faster).
 const BEER_SPEED_DIV: u32 = 4;
-// Beer byte velocity (Q8.8 LEDs/frame) in BEER MANUAL: fast, but not instant.
-const BEER_MANUAL_VEL_Q8: i32 = 4 * 256;
+// Beer packet velocity (Q8.8 LEDs/frame) in BEER MANUAL: fast, but not instant.
+const BEER_MANUAL_VEL_Q8: u32 = 4 * 256;
+// The beer packet leaves a motion-blur trail fading with this time constant
+// (a short glow when slow, a streak in BEER MANUAL).
+const BEER_TRAIL_MS: u32 = 40;
+// While the IMU runs, the BEER strip shows its stream faintly under the beer
+// byte (~25%).
+const BEER_UNDERLAY_Q16: u32 = 16384;
+// With a tempo set, MUSIC, PARTY and ambient shows pulse on the beat: full
+// brightness as each beat starts, down to this floor (~40%) by the next. Beats
+// other than the downbeat peak lower.
+const BEAT_FLOOR_Q16: u32 = 26214;
+const OFFBEAT_PEAK_Q16: u32 = 49152;
 
 /// Each physical strip is dedicated to one process.
 #[derive(Clone, Copy, PartialEq)]
@@ -33,167 +63,584 @@ pub enum StripRole {
     Beer,
     Imu,
     Music,
+    /// A matrix panel (see `StripConfig::with_matrix`): the FLUIDS water.
+    Matrix,
 }
 
-const MUSIC_COLOR: Rgb = [0, 255, 90]; // green pulse while music plays
-const MUSIC_PERIOD_MS: u32 = 2000; // breathing cycle
+/// Library effects an idle strip cycles through.
+#[derive(Clone, Copy, PartialEq)]
+enum Ambient {
+    Comet,
+    Twinkle,
+    Fire,
+    Larson,
+    Chase,
+    Plasma,
+    Plasma2d,
+}
 
-#[embassy_executor::task(pool_size = 3)]
-pub async fn run(mut strip: Ws2812, role: StripRole) {
-    let mut fb: Framebuffer = [[0, 0, 0]; NUM_LEDS];
+// Idle playlists: what each role's strip plays while its process is off (and
+// ambient mode is on), each show crossfading into the next.
+const AMBIENT_FADE: Transition = Transition::crossfade(2000);
+const BEER_IDLE: [Entry<Ambient>; 2] = [
+    Entry::new(Ambient::Fire, 40_000, AMBIENT_FADE),
+    Entry::new(Ambient::Twinkle, 20_000, AMBIENT_FADE),
+];
+const IMU_IDLE: [Entry<Ambient>; 3] = [
+    Entry::new(Ambient::Larson, 20_000, AMBIENT_FADE),
+    Entry::new(Ambient::Comet, 20_000, AMBIENT_FADE),
+    Entry::new(Ambient::Chase, 20_000, AMBIENT_FADE),
+];
+const MUSIC_IDLE: [Entry<Ambient>; 2] = [
+    Entry::new(Ambient::Plasma, 40_000, AMBIENT_FADE),
+    Entry::new(Ambient::Twinkle, 20_000, AMBIENT_FADE),
+];
+const MATRIX_IDLE: [Entry<Ambient>; 2] = [
+    Entry::new(Ambient::Plasma2d, 40_000, AMBIENT_FADE),
+    Entry::new(Ambient::Twinkle, 20_000, AMBIENT_FADE),
+];
 
-    let mut stream = Stream::new();
-    let mut beer = BeerByte::new(BEER_BYTE);
-    let mut beer_was_on = false;
+fn idle_playlist(role: StripRole) -> &'static [Entry<Ambient>] {
+    match role {
+        StripRole::Beer => &BEER_IDLE,
+        StripRole::Imu => &IMU_IDLE,
+        StripRole::Music => &MUSIC_IDLE,
+        StripRole::Matrix => &MATRIX_IDLE,
+    }
+}
 
-    loop {
-        let now = Instant::now().as_millis() as u32;
-        fb.fill([0, 0, 0]);
+/// Any supported strip type behind one `write(fb)` API.
+pub enum Strip {
+    OneWire(Ws2812),
+    Spi(Apa102),
+}
 
-        // PARTY easter egg overrides every strip with a rainbow.
-        if control::party_on() {
-            party_render(&mut fb, role, now);
-            strip.write(&fb);
-            Timer::after(Duration::from_millis(FRAME_MS)).await;
-            continue;
-        }
-
-        match role {
-            // IMU strip: the byte stream while the IMU is on, brightness ramping
-            // up after it starts.
-            StripRole::Imu => {
-                if control::imu_on() {
-                    stream.set_velocity_q8(control::velocity_q8());
-                    stream.render(&mut fb);
-                    let ramp = control::imu_ramp_q8(Instant::now().as_millis() as u32);
-                    if ramp < 256 {
-                        for px in fb.iter_mut() {
-                            for c in px.iter_mut() {
-                                *c = (*c as u32 * ramp / 256) as u8;
-                            }
-                        }
-                    }
+impl Strip {
+    pub fn num_leds(&self) -> usize {
+        match self {
+            Strip::OneWire(s) => s.num_leds(),
+            Strip::Spi(s) => s.num_leds(),
+        }
+    }
+
+    /// Time (us) a frame takes on the wire.
+    pub fn frame_us(&self) -> u32 {
+        match self {
+            Strip::OneWire(s) => s.frame_us(),
+            Strip::Spi(s) => s.frame_us(),
+        }
+    }
+
+    pub async fn write(&mut self, fb: &Framebuffer) {
+        self.begin_write(fb);
+        self.end_write().await;
+    }
+
+    /// Encode `fb`; one-wire strips start transmitting right away.
+    pub fn begin_write(&mut self, fb: &Framebuffer) {
+        match self {
+            Strip::OneWire(s) => s.begin_write(fb),
+            Strip::Spi(s) => s.begin_write(fb),
+        }
+    }
+
+    /// Resolves once the frame is out; SPI strips transmit here (while the
+    /// one-wire strips begun before keep streaming).
+    pub async fn end_write(&mut self) {
+        match self {
+            Strip::OneWire(s) => s.end_write().await,
+            Strip::Spi(s) => s.end_write().await,
+        }
+    }
+
+    /// Apply the driver-side parts of `config` (colour order, brightness cap).
+    fn configure(&mut self, config: &StripConfig) {
+        match self {
+            Strip::OneWire(s) => {
+                if let Some(order) = config.order {
+                    s.set_color_order(order);
                 }
+                s.set_brightness_cap(config.brightness_cap);
             }
+            Strip::Spi(s) => s.set_brightness_cap(config.brightness_cap),
+        }
+    }
+}
 
-            // BEER strip: a single distinct-color byte travelling the strip; it
-            // also signals the servo when it reaches the end.
-            StripRole::Beer => {
-                let beer_on = control::beer_on();
-                if beer_on && !beer_was_on {
-                    beer.reset(); // rising edge -> fire a new shot
-                }
-                beer_was_on = beer_on;
-                if beer_on {
-                    let beer_vel = if control::manual_on() {
-                        BEER_MANUAL_VEL_Q8
-                    } else {
-                        (control::velocity_q8() / BEER_SPEED_DIV).max(1) as i32
-                    };
-                    beer.overlay(&mut fb, beer_vel);
-                    if beer.finished() {
-                        control::signal_beer_arrived();
-                        control::clear_beer();
+impl From<Ws2812> for Strip {
+    fn from(s: Ws2812) -> Self {
+        Strip::OneWire(s)
+    }
+}
+
+impl From<Apa102> for Strip {
+    fn from(s: Apa102) -> Self {
+        Strip::Spi(s)
+    }
+}
+
+/// How the tapped bus bytes are framed on the strip (`control::framing()`).
+#[derive(Clone, Copy, PartialEq)]
+enum Wire {
+    Raw,
+    Uart,
+    I2c,
+    Spi,
+}
+
+impl Wire {
+    const ALL: [Wire; 4] = [Wire::Raw, Wire::Uart, Wire::I2c, Wire::Spi];
+
+    fn current() -> Self {
+        match control::framing() {
+            1 => Wire::Uart,
+            2 => Wire::I2c,
+            3 => Wire::Spi,
+            _ => Wire::Raw,
+        }
+    }
+}
+
+/// What a strip is showing; each maps to one effect instance in [`Effects`].
+#[derive(Clone, Copy, PartialEq)]
+enum Show {
+    Stream(Wire),
+    Beer,
+    Music,
+    Party,
+    Fluid,
+    Ambient(Ambient),
+}
+
+/// Shows with tunable parameters, in Effect settings order: the show, its
+/// menu label and its parameters. The stream's apply to every framing.
+const TUNABLE: [(Show, &str, &[Param]); 4] = [
+    (Show::Stream(Wire::Raw), "Stream", Gaps::PARAMS),
+    (Show::Beer, "Beer", BeerByte::PARAMS),
+    (Show::Party, "Party", Party::PARAMS),
+    (Show::Music, "Music", Spectrum::PARAMS),
+];
+
+/// Number of Effect settings (every parameter of every [`TUNABLE`] show).
+pub const SETTING_COUNT: usize = {
+    let mut n = 0;
+    let mut i = 0;
+    while i < TUNABLE.len() {
+        n += TUNABLE[i].2.len();
+        i += 1;
+    }
+    n
+};
+const _: () = assert!(SETTING_COUNT <= control::EFFECT_PARAMS);
+
+/// (show, parameter index, label, parameter) for each Effect setting, in
+/// `control::effect_param` slot order.
+fn settings() -> impl Iterator<Item = (Show, usize, &'static str, Param)> {
+    TUNABLE.iter().flat_map(|&(show, label, params)| {
+        params.iter().enumerate().map(move |(i, &p)| (show, i, label, p))
+    })
+}
+
+/// Effect setting `index` (below [`SETTING_COUNT`]): its show's label and the
+/// parameter. The value is `control::effect_param(index)`.
+pub fn setting(index: usize) -> Option<(&'static str, Param)> {
+    settings().nth(index).map(|(_, _, label, p)| (label, p))
+}
+
+type Tap = TapReader<{ bus::I2C_TAP_LEN }>;
+
+/// One strip's effect instances.
+struct Effects {
+    stream: Stream<Tap>,
+    uart: Framed<Uart, Tap>,
+    i2c: Framed<I2c, Tap>,
+    spi: Framed<Spi, Tap>,
+    beer: BeerByte,
+    music: Spectrum,
+    party: Party,
+    comet: Comet,
+    twinkle: Twinkle,
+    fire: Fire,
+    larson: Larson,
+    chase: Chase,
+    plasma: Plasma,
+    plasma2d: Plasma2d,
+    fluid: Occupancy,
+}
+
+impl Effects {
+    /// Start showing `show`. Each BEER shot carries the servo packet it
+    /// announces.
+    fn enter(&mut self, show: Show, ctx: &Ctx) {
+        if show == Show::Beer {
+            self.beer.set_bytes(&servo::next_packet());
+        }
+        self.get(show).on_enter(ctx);
+    }
+
+    fn get(&mut self, show: Show) -> &mut dyn Effect {
+        match show {
+            Show::Stream(Wire::Raw) => &mut self.stream,
+            Show::Stream(Wire::Uart) => &mut self.uart,
+            Show::Stream(Wire::I2c) => &mut self.i2c,
+            Show::Stream(Wire::Spi) => &mut self.spi,
+            Show::Beer => &mut self.beer,
+            Show::Music => &mut self.music,
+            Show::Party => &mut self.party,
+            Show::Fluid => &mut self.fluid,
+            Show::Ambient(Ambient::Comet) => &mut self.comet,
+            Show::Ambient(Ambient::Twinkle) => &mut self.twinkle,
+            Show::Ambient(Ambient::Fire) => &mut self.fire,
+            Show::Ambient(Ambient::Larson) => &mut self.larson,
+            Show::Ambient(Ambient::Chase) => &mut self.chase,
+            Show::Ambient(Ambient::Plasma) => &mut self.plasma,
+            Show::Ambient(Ambient::Plasma2d) => &mut self.plasma2d,
+        }
+    }
+
+    /// Publish these effects' parameters as the settings' starting values.
+    fn publish_settings(&mut self) {
+        for (slot, (show, i, _, _)) in settings().enumerate() {
+            control::set_effect_param(slot, self.get(show).param(i));
+        }
+    }
+
+    /// Apply the Effect settings from `control`.
+    fn apply_settings(&mut self) {
+        for (slot, (show, i, _, _)) in settings().enumerate() {
+            let value = control::effect_param(slot);
+            match show {
+                Show::Stream(_) => {
+                    for wire in Wire::ALL {
+                        self.get(Show::Stream(wire)).set_param(i, value);
                     }
                 }
+                _ => self.get(show).set_param(i, value),
             }
+        }
+    }
+}
 
-            // MUSIC strip: a gentle breathing pulse while music plays. Phase from
-            // wall-clock time (not a per-frame counter), so it stays smooth even if
-            // core1's frame cadence stutters. (Placeholder until the multi-track
-            // music refactor.)
-            StripRole::Music => {
-                if control::music_on() {
-                    let t = Instant::now().as_millis() as u32;
-                    let phase = (t % MUSIC_PERIOD_MS) * 256 / MUSIC_PERIOD_MS; // 0..255
-                    let tri = if phase < 128 { phase * 2 } else { (255 - phase) * 2 };
-                    let level = 40 + tri * 215 / 255; // keep a dim floor
-                    let color = [
-                        (MUSIC_COLOR[0] as u32 * level / 255) as u8,
-                        (MUSIC_COLOR[1] as u32 * level / 255) as u8,
-                        (MUSIC_COLOR[2] as u32 * level / 255) as u8,
-                    ];
-                    fb.fill(color);
-                }
-            }
+/// Deepest layer stack of any strip.
+const LAYERS: usize = 2;
+
+/// What `role` should show right now, bottom layer first (all None = idle).
+fn wanted(role: StripRole) -> [Option<Show>; LAYERS] {
+    let stream = Show::Stream(Wire::current());
+    // PARTY easter egg overrides every strip with a rainbow; a pour started
+    // from it still carries its packets over the BEER strip.
+    if control::party_on() {
+        let beer = role == StripRole::Beer && control::beer_on();
+        return [Some(Show::Party), beer.then_some(Show::Beer)];
+    }
+    match role {
+        StripRole::Imu => [control::imu_on().then_some(stream), None],
+        StripRole::Music => [control::music_on().then_some(Show::Music), None],
+        StripRole::Matrix => [control::fluids_on().then_some(Show::Fluid), None],
+        // The beer packet glides over the IMU stream.
+        StripRole::Beer => [
+            control::imu_on().then_some(stream),
+            control::beer_on().then_some(Show::Beer),
+        ],
+    }
+}
+
+/// One layer of a strip's stack.
+#[derive(Clone, Copy, Default)]
+struct Slot {
+    show: Option<Show>,
+    /// Running transition and the effect it fades out (None = from dark).
+    fade: Option<(Option<Show>, Fade)>,
+}
+
+/// Transition into `to` on a strip configured with `config`.
+fn transition_to(to: Option<Show>, config: &StripConfig, now_ms: u32) -> Transition {
+    let ramp = control::imu_ramp_q8(now_ms);
+    match to {
+        // The IMU stream fades in over the rest of the IMU start-up ramp.
+        Some(Show::Stream(_)) if ramp < 256 => {
+            Transition::crossfade((256 - ramp) * control::IMU_RAMP_MS / 256)
         }
+        _ => config.transition,
+    }
+}
 
-        strip.write(&fb);
-        Timer::after(Duration::from_millis(FRAME_MS)).await;
+/// Per-show tick context and opacity on `role`'s strip.
+fn layer_params(show: Show, role: StripRole, ctx: &Ctx) -> (Ctx, u32) {
+    match show {
+        // Faint underlay for the beer packet.
+        Show::Stream(_) if role == StripRole::Beer => (*ctx, BEER_UNDERLAY_Q16),
+        // BEER: the servo packet in a distinct color travelling the strip,
+        // slower than the stream (fast in BEER MANUAL).
+        Show::Beer => {
+            let velocity_q8 = if control::manual_on() {
+                BEER_MANUAL_VEL_Q8
+            } else {
+                (ctx.velocity_q8 / BEER_SPEED_DIV).max(1)
+            };
+            let ctx = Ctx {
+                velocity_q8,
+                ..*ctx
+            };
+            (ctx, 65535)
+        }
+        Show::Music | Show::Party | Show::Ambient(_) => (*ctx, beat_pulse_q16(ctx)),
+        _ => (*ctx, 65535),
     }
 }
 
-/// PARTY: a fast-scrolling rainbow with a tilt-driven overlay. The BEER strip
-/// shows a bright white "gravity ball" (framed by an off-LED on each side for
-/// pop) rolling to the downhill end; the other strips show a "liquid level"
-/// that fills up to the tilt angle. Shake (accel) blends everything white.
-fn party_render(fb: &mut Framebuffer, role: StripRole, now: u32) {
-    let role_off: u32 = match role {
-        StripRole::Beer => 0,
-        StripRole::Imu => 85,
-        StripRole::Music => 170,
+/// Opacity of a beat-synced show: see BEAT_FLOOR_Q16 (opaque with no tempo).
+fn beat_pulse_q16(ctx: &Ctx) -> u32 {
+    let Some(beat) = ctx.beat else {
+        return 65535;
     };
-    let scroll = now / 8; // fast rainbow scroll
-    // Tilt (roll -90..90) -> a position along the 60-LED strip.
-    let roll = control::roll().clamp(-90, 90);
-    let pos = (roll + 90) * (NUM_LEDS as i32 - 1) / 180; // 0..NUM_LEDS-1
-    // Shake -> white blend.
-    let (ax, ay, az) = (control::accel_x(), control::accel_y(), control::accel_z());
-    let mag = fabsf(sqrtf(ax * ax + ay * ay + az * az) - 1.0);
-    let strobe = (mag.clamp(0.0, 1.0) * 255.0) as u32; // 0..255
-    let shift = control::brightness_shift();
-    let ball = role == StripRole::Beer;
-    const BALL_R: i32 = 4; // solid white blob half-width
-    const BALL_GAP: i32 = 2; // off-LEDs framing each side
-
-    for (i, px) in fb.iter_mut().enumerate() {
-        let hue = (scroll + role_off + (i as u32 * 256 / NUM_LEDS as u32)) as u8;
-        let (r0, g0, b0) = hsv_to_rgb(hue, 255, 220);
-        let idx = i as i32;
-
-        // White amount (0..255) and brightness (0..255) from the tilt overlay.
-        let (white, bright) = if ball {
-            let d = (idx - pos).abs();
-            if d <= BALL_R {
-                (255, 255) // solid bright-white ball (high contrast)
-            } else if d <= BALL_R + BALL_GAP {
-                (0, 0) // dark frame on each side so the ball stands out
-            } else {
-                (0, 14) // very dim rainbow background
+    let peak = if beat.is_downbeat() {
+        65535
+    } else {
+        OFFBEAT_PEAK_Q16
+    };
+    BEAT_FLOOR_Q16 + (peak - BEAT_FLOOR_Q16) * beat.pulse_q16() / 65535
+}
+
+/// One strip with its config, effects and frame; [`run`] renders them all.
+pub struct Renderer {
+    strip: Strip,
+    config: StripConfig,
+    buf: [Rgb; MAX_LEDS],
+    fx: Effects,
+    slots: [Slot; LAYERS],
+    idle: Playlist<Ambient>,
+    /// Last BEER shot seen, and whether its packet has reached the strip end.
+    shot: u32,
+    shot_landed: bool,
+}
+
+impl Renderer {
+    pub fn new(mut strip: Strip, config: StripConfig) -> Self {
+        strip.configure(&config);
+        let len = strip.num_leds();
+        let role = config.role;
+        let hue_off = match role {
+            StripRole::Beer => 0,
+            StripRole::Imu => 85,
+            StripRole::Music => 170,
+            StripRole::Matrix => 42,
+        };
+        // 2D effects on a plain strip see it as a one-row matrix.
+        let map = config
+            .matrix
+            .unwrap_or(XyMap::new(len, 1, Layout::RowMajor));
+        Self {
+            strip,
+            config,
+            buf: [[0, 0, 0]; MAX_LEDS],
+            fx: Effects {
+                stream: Stream::with_source(len, bus::I2C_TAP.reader()),
+                uart: Framed::with_source(len, Uart, bus::I2C_TAP.reader()),
+                i2c: Framed::with_source(len, I2c::new(), bus::I2C_TAP.reader()),
+                spi: Framed::with_source(len, Spi, bus::I2C_TAP.reader()),
+                beer: BeerByte::new(0).with_trail(BEER_TRAIL_MS),
+                music: Spectrum::new(),
+                party: Party::new(hue_off, role == StripRole::Beer),
+                comet: Comet::new(),
+                twinkle: Twinkle::new(),
+                fire: Fire::new(),
+                larson: Larson::new(),
+                chase: Chase::new(),
+                plasma: Plasma::new(),
+                plasma2d: Plasma2d::new(map),
+                fluid: Occupancy::new(map),
+            },
+            slots: [Slot::default(); LAYERS],
+            idle: Playlist::new(idle_playlist(role)),
+            shot: control::beer_shot(),
+            shot_landed: true,
+        }
+    }
+
+    /// Render this strip's frame for `ctx` (its `len` is filled in here).
+    fn render(&mut self, comp: &mut Compositor<'_>, ctx: &Ctx) {
+        let role = self.config.role;
+        let fb = &mut self.buf[..self.strip.num_leds()];
+        let ctx = Ctx {
+            len: fb.len(),
+            ..*ctx
+        };
+        let fx = &mut self.fx;
+        fx.apply_settings();
+        // The FLUIDS water as the display task last stepped it.
+        fx.fluid.set_grid(fluid::GRID_W, &fluid::grid());
+
+        // An idle strip plays its ambient playlist on the bottom layer, from
+        // the top each time it goes idle.
+        let mut want = wanted(role);
+        let busy = want.iter().any(Option::is_some);
+        let mut ambient_fade = Transition::CUT;
+        if !busy && control::ambient_on() {
+            self.idle.tick(ctx.dt_ms);
+            if let Some(entry) = self.idle.current() {
+                want[0] = Some(Show::Ambient(entry.item));
+                ambient_fade = entry.fade;
             }
-        } else if idx <= pos {
-            (0, 255) // liquid: bright up to the level
         } else {
-            (0, 45) // liquid: dim above
-        };
-        let bright = bright.max(strobe); // a hard shake lights the whole strip
-        let w = white.max(strobe);
+            self.idle.restart();
+        }
+
+        let shot = control::beer_shot();
+        let new_shot = shot != self.shot;
+        if new_shot {
+            self.shot = shot;
+            self.shot_landed = false;
+        }
+
+        // Lifecycle: enter the new effect and fade the old one out; it exits
+        // once the fade is done. A change mid-fade drops the effect that was
+        // already fading out.
+        for (slot, &want) in self.slots.iter_mut().zip(want.iter()) {
+            if want == slot.show {
+                // A shot fired over the last one's packet restarts it.
+                if new_shot && want == Some(Show::Beer) {
+                    fx.enter(Show::Beer, &ctx);
+                }
+                continue;
+            }
+            if let Some((Some(old), _)) = slot.fade.take() {
+                fx.get(old).on_exit();
+            }
+            if let Some(new) = want {
+                fx.enter(new, &ctx);
+            }
+            let transition = match (slot.show, want) {
+                (_, Some(Show::Ambient(_))) => ambient_fade,
+                // Ambient hands the strip back to its process at once.
+                (Some(Show::Ambient(_)), _) if busy => Transition::CUT,
+                _ => transition_to(want, &self.config, ctx.now_ms),
+            };
+            if transition.is_cut() {
+                if let Some(old) = slot.show {
+                    fx.get(old).on_exit();
+                }
+            } else {
+                slot.fade = Some((slot.show, Fade::new(transition)));
+            }
+            slot.show = want;
+        }
+
+        fb.fill([0, 0, 0]);
+        for slot in self.slots.iter_mut() {
+            let (outgoing, incoming) = match &mut slot.fade {
+                Some((from, fade)) => {
+                    fade.tick(ctx.dt_ms);
+                    let out = from.map(|old| (old, fade.outgoing(slot.show.is_some())));
+                    (out, fade.incoming())
+                }
+                None => (None, (65535, Mask::All)),
+            };
+            let incoming = slot.show.map(|new| (new, incoming));
+            for (show, (opacity_q16, mask)) in outgoing.into_iter().chain(incoming) {
+                let (ctx, base_q16) = layer_params(show, role, &ctx);
+                let layer = Layer::new(fx.get(show))
+                    .with_opacity_q16(base_q16 * opacity_q16 / 65535)
+                    .with_mask(mask);
+                comp.draw(layer, &ctx, fb);
+            }
+            if let Some((from, fade)) = slot.fade {
+                if fade.is_done() {
+                    if let Some(old) = from {
+                        fx.get(old).on_exit();
+                    }
+                    slot.fade = None;
+                }
+            }
+        }
+
+        // The packet's checksum leaving the strip end signals the servo, once
+        // for the shot in flight.
+        let beer_shown = self.slots.iter().any(|s| s.show == Some(Show::Beer));
+        if beer_shown && !self.shot_landed && fx.beer.is_finished() {
+            self.shot_landed = true;
+            control::signal_beer_arrived();
+            control::clear_beer(self.shot);
+        }
+
+        // Effects render from the data-in end; a strip mounted the other way
+        // round just gets the frame mirrored (every frame is rendered afresh).
+        if self.config.reverse {
+            fb.reverse();
+        }
+    }
+
+    fn begin_write(&mut self) {
+        let n = self.strip.num_leds();
+        self.strip.begin_write(&self.buf[..n]);
+    }
+}
+
+/// Frame period for strips sent in lock-step: render time plus the longest
+/// strip's transmission, in whole ms and at least `FRAME_MS` (a 300-LED
+/// WS2812 strip alone is 9 ms on the wire).
+fn frame_period(strips: &[Renderer]) -> Duration {
+    let wire_us = strips.iter().map(|r| r.strip.frame_us()).max().unwrap_or(0);
+    let ms = (RENDER_US + wire_us).div_ceil(1000).max(FRAME_MS);
+    Duration::from_millis(ms as u64)
+}
+
+/// Render loop for all strips: every frame renders each strip for the same
+/// timestamp, starts all transmissions together and waits for them, paced
+/// against a fixed deadline sized by [`frame_period`]. Frames that cannot
+/// make it are dropped (counted in `control`) rather than stretching the ones
+/// after.
+#[embassy_executor::task]
+pub async fn run(strips: &'static mut [Renderer]) {
+    let mut comp = Compositor::new().expect("run is spawned once");
+    let frame = frame_period(strips);
+    let mut deadline = Instant::now();
+    let mut last_ms = deadline.as_millis() as u32;
+    let mut theme = PaletteFade::new(control::theme().palette());
+    // Every strip starts with the same parameters; the first one's seed the
+    // Effect settings.
+    if let Some(r) = strips.first_mut() {
+        r.fx.publish_settings();
+    }
 
-        let mix = |c: u8| -> u8 {
-            let lit = c as u32 * bright / 255; // apply brightness
-            let lifted = lit + (255 - lit) * w / 255; // blend toward white
-            (lifted as u8) >> shift
+    loop {
+        let started = Instant::now();
+        let now = started.as_millis() as u32;
+        let dt_ms = now.wrapping_sub(last_ms).min(MAX_DT_MS);
+        theme.set_target(control::theme().palette(), THEME_FADE);
+        theme.tick(dt_ms);
+        let ctx = Ctx {
+            now_ms: now,
+            dt_ms,
+            len: 0,
+            velocity_q8: control::velocity_q8(),
+            roll: control::roll(),
+            accel: [control::accel_x(), control::accel_y(), control::accel_z()],
+            palette: theme.current(),
+            audio: control::audio(now),
+            beat: control::tempo().map(|t| t.at(now)),
         };
-        *px = [mix(r0), mix(g0), mix(b0)];
-    }
-}
-
-/// 8-bit HSV -> RGB (hue 0..255).
-fn hsv_to_rgb(h: u8, s: u8, v: u8) -> (u8, u8, u8) {
-    if s == 0 {
-        return (v, v, v);
-    }
-    let region = (h / 43) as u32;
-    let rem = (h % 43) as u32 * 6; // 0..252
-    let p = (v as u32 * (255 - s as u32) / 255) as u8;
-    let q = (v as u32 * (255 - s as u32 * rem / 255) / 255) as u8;
-    let t = (v as u32 * (255 - s as u32 * (255 - rem) / 255) / 255) as u8;
-    match region {
-        0 => (v, t, p),
-        1 => (q, v, p),
-        2 => (p, v, t),
-        3 => (p, q, v),
-        4 => (t, p, v),
-        _ => (v, p, q),
+        last_ms = now;
+
+        for r in strips.iter_mut() {
+            r.render(&mut comp, &ctx);
+        }
+        control::set_led_render_us(started.elapsed().as_micros() as u32);
+
+        for r in strips.iter_mut() {
+            r.begin_write();
+        }
+        for r in strips.iter_mut() {
+            r.strip.end_write().await;
+        }
+
+        deadline += frame;
+        let now = Instant::now();
+        if now > deadline {
+            let missed = ((now - deadline).as_ticks() / frame.as_ticks() + 1) as u32;
+            control::add_led_frames_dropped(missed);
+            deadline += frame * missed;
+        }
+        Timer::at(deadline).await;
     }
 }
@@ -0,0 +1,38 @@
+//! LED supply budget. Every strip reports the estimated current of its encoded
+//! frame; when the demand of all strips together exceeds
+//! `control::led_budget_ma()`, each frame is scaled down by the same factor so
+//! the total lands on the budget (full-white PARTY shake strobe is the worst
+//! case). Only the lit part scales: the LEDs' quiescent draw is taken off the
+//! budget first (see `led_fx::power`). The estimated draw is published to
+//! `control`.
+
+use core::sync::atomic::{AtomicU32, Ordering::Relaxed};
+
+pub use led_fx::power::{estimate_ma, idle_ma};
+
+use crate::control;
+
+/// One slot per strip: RMT strips use their channel (0..8), SPI strips 8..10.
+pub const SLOTS: usize = 10;
+static DEMAND_MA: [AtomicU32; SLOTS] = [const { AtomicU32::new(0) }; SLOTS];
+static IDLE_MA: [AtomicU32; SLOTS] = [const { AtomicU32::new(0) }; SLOTS];
+static DRAW_MA: [AtomicU32; SLOTS] = [const { AtomicU32::new(0) }; SLOTS];
+
+fn total(slots: &[AtomicU32; SLOTS]) -> u32 {
+    slots.iter().map(|m| m.load(Relaxed)).sum()
+}
+
+/// Publish strip `slot`'s demand (`idle_ma` of it quiescent) and get the Q16
+/// factor (65536 = 1) its duties must be scaled by. The other strips' figures
+/// are from their last frame (at most one frame old).
+pub fn request(slot: usize, demand_ma: u32, idle_ma: u32) -> u32 {
+    DEMAND_MA[slot].store(demand_ma, Relaxed);
+    IDLE_MA[slot].store(idle_ma, Relaxed);
+    led_fx::power::scale_q16(total(&DEMAND_MA), total(&IDLE_MA), control::led_budget_ma())
+}
+
+/// Publish strip `slot`'s draw after scaling; updates the total in `control`.
+pub fn report(slot: usize, draw_ma: u32) {
+    DRAW_MA[slot].store(draw_ma, Relaxed);
+    control::set_led_current_ma(total(&DRAW_MA));
+}
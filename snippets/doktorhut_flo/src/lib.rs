@@ -1,6 +1,7 @@
 #![no_std]
 
 pub mod about;
+pub mod audio;
 pub mod bus;
 pub mod control;
 pub mod dfplayer;
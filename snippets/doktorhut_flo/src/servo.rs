@@ -1,5 +1,7 @@
 //! FeeTech STS3215 servo on UART2 / GPIO14 (1 Mbps, half-duplex, TX only --
 //! a WRITE has no reply). On a BEER trigger it runs a fixed position sequence.
+//! Each step's packet first travels the BEER strip (`next_packet`) and is sent
+//! when its checksum reaches the strip end.
 
 use embassy_time::{Duration, Timer};
 use esp_hal::uart::UartTx;
@@ -16,7 +18,7 @@ const SEQUENCE: [u16; 3] = [2000, 0920, 3072];
 const STEP_MS: u64 = 2000;
 
 /// Frame: 0xFF 0xFF ID LEN INSTR ADDR POS_L POS_H CHECKSUM.
-fn goal_position_packet(id: u8, pos: u16) -> [u8; 9] {
+pub fn goal_position_packet(id: u8, pos: u16) -> [u8; 9] {
     let pos_l = (pos & 0xFF) as u8;
     let pos_h = (pos >> 8) as u8;
     let len = 5; // 3 params + 2
@@ -39,6 +41,17 @@ fn goal_position_packet(id: u8, pos: u16) -> [u8; 9] {
     ]
 }
 
+/// The packet the next BEER shot carries: the current pour step's, or the
+/// manual target's in BEER MANUAL.
+pub fn next_packet() -> [u8; 9] {
+    let pos = if control::manual_on() {
+        control::servo_pos() as u16
+    } else {
+        SEQUENCE[(control::pour_step() as usize).min(SEQUENCE.len() - 1)]
+    };
+    goal_position_packet(SERVO_ID, pos)
+}
+
 async fn drive_to(tx: &mut UartTx<'static, Async>, pos: u16) {
     let pkt = goal_position_packet(SERVO_ID, pos);
     if tx.write_async(&pkt).await.is_ok() {
@@ -51,23 +64,31 @@ pub async fn run(mut tx: UartTx<'static, Async>) {
     let mut manual_was = false;
     let mut last_pos = 0u16;
     loop {
-        // Start only when the beer byte has visually travelled the strip and
-        // reached the servo. Travel time follows NUM_LEDS x the configurable
-        // LED speed, so the sync stays correct for any strip length / speed.
-        // Consume the arrival event, but only run the pour sequence outside
-        // manual mode (in manual the byte is just a visual flourish per tick).
+        // Send a pour step only when its packet has visually travelled the
+        // strip and reached the servo. Travel time follows the BEER strip's
+        // length x the configurable LED speed, so the sync stays correct for
+        // any length / speed. Consume the arrival event, but only pour outside
+        // manual mode (in manual the packet is just a visual flourish per tick).
         let arrived = control::take_beer_arrived();
         if arrived && !control::manual_on() {
             control::set_beer_pouring(true); // keep BEER indicator lit during pour
-            for &pos in SEQUENCE.iter() {
-                drive_to(&mut tx, pos).await;
-                Timer::after(Duration::from_millis(STEP_MS)).await;
+            let step = (control::pour_step() as usize).min(SEQUENCE.len() - 1);
+            drive_to(&mut tx, SEQUENCE[step]).await;
+            // The next step's packet sets off while this one holds; it is
+            // sent on arrival, but not before the hold is over.
+            let next = step + 1;
+            if next < SEQUENCE.len() {
+                control::send_pour_step(next as u32);
+            }
+            Timer::after(Duration::from_millis(STEP_MS)).await;
+            if next == SEQUENCE.len() {
+                control::set_beer_pouring(false);
             }
-            control::set_beer_pouring(false);
         }
         // BEER MANUAL: drive only when the encoder actually changes the target.
         // On entry, sync the last value without moving (don't jump on open).
         if control::manual_on() {
+            control::set_beer_pouring(false); // manual abandons a pour
             let pos = control::servo_pos() as u16;
             if !manual_was {
                 last_pos = pos;
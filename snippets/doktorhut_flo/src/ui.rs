@@ -6,8 +6,9 @@ use core::cell::RefCell;
 
 use critical_section::Mutex;
 use embassy_time::Instant;
+use led_fx::tempo::TapTempo;
 
-use crate::control;
+use crate::{control, led_strip};
 
 fn now_ms() -> u32 {
     Instant::now().as_millis() as u32
@@ -24,6 +25,7 @@ pub enum Event {
 enum Screen {
     Main,
     Controls,
+    Effects,
     Fluids,
     Tilt,
     About,
@@ -35,6 +37,7 @@ enum Screen {
 pub enum ViewScreen {
     Main,
     Controls,
+    Effects,
     Fluids,
     Tilt,
     About,
@@ -45,7 +48,14 @@ pub enum ViewScreen {
 pub const MAIN_ITEMS: [&str; 8] = [
     "BEER", "BEER MAN", "MUSIC", "IMU", "FLUIDS", "TILT", "ABOUT", "CONTROLS",
 ];
-pub const CONTROL_ITEMS: [&str; 4] = ["Volume", "LED Speed", "LED Bright", "Back"];
+pub const CONTROL_ITEMS: [&str; 10] = [
+    "Volume", "LED Speed", "LED Bright", "Framing", "Theme", "Ambient", "Tempo", "Power", "Effects",
+    "Back",
+];
+/// Rows of the Effect settings screen: one per `led_strip::setting`, then Back.
+pub const EFFECT_ITEMS: usize = led_strip::SETTING_COUNT + 1;
+/// Names of the stream framings, indexed by `control::framing()`.
+pub const FRAMING_NAMES: [&str; control::FRAMING_COUNT as usize] = ["Raw", "UART", "I2C", "SPI"];
 
 /// The first PROCESS_COUNT MAIN_ITEMS are "processes" (highlight selection +
 /// status rects); the rest are normal entries (">" cursor).
@@ -61,13 +71,21 @@ const MAIN_FLUIDS: usize = 4;
 const MAIN_TILT: usize = 5;
 const MAIN_ABOUT: usize = 6;
 const MAIN_CONTROLS: usize = 7;
-const CONTROLS_BACK: usize = 3; // index of "Back" in CONTROL_ITEMS
+const CONTROLS_TEMPO: usize = 6; // index of "Tempo" in CONTROL_ITEMS
+const CONTROLS_POWER: usize = 7; // index of "Power" in CONTROL_ITEMS
+const CONTROLS_EFFECTS: usize = 8; // index of "Effects" in CONTROL_ITEMS
+const CONTROLS_BACK: usize = 9; // index of "Back" in CONTROL_ITEMS
+const EFFECTS_BACK: usize = EFFECT_ITEMS - 1;
+// Editing the tempo (tapping it in) ends this long after the last input.
+const TEMPO_EDIT_MS: u32 = 3000;
 
 struct Ui {
     screen: Screen,
     cursor: usize,
     editing: bool,
     pending: Option<Screen>, // screen to auto-enter once the IMU is ready
+    taps: TapTempo,
+    last_input_ms: u32, // last input while editing (for the tempo timeout)
 }
 
 static UI: Mutex<RefCell<Ui>> = Mutex::new(RefCell::new(Ui {
@@ -75,6 +93,8 @@ static UI: Mutex<RefCell<Ui>> = Mutex::new(RefCell::new(Ui {
     cursor: 0,
     editing: false,
     pending: None,
+    taps: TapTempo::new(),
+    last_input_ms: 0,
 }));
 
 /// Snapshot for the display.
@@ -91,6 +111,7 @@ pub fn view() -> View {
             screen: match ui.screen {
                 Screen::Main => ViewScreen::Main,
                 Screen::Controls => ViewScreen::Controls,
+                Screen::Effects => ViewScreen::Effects,
                 Screen::Fluids => ViewScreen::Fluids,
                 Screen::Tilt => ViewScreen::Tilt,
                 Screen::About => ViewScreen::About,
@@ -113,7 +134,7 @@ pub fn on_input(ev: Event) {
                 Event::Left => ui.cursor = wrap_prev(ui.cursor, MAIN_ITEMS.len()),
                 Event::Right => ui.cursor = wrap_next(ui.cursor, MAIN_ITEMS.len()),
                 Event::Click => match ui.cursor {
-                    MAIN_BEER => control::start_beer(),
+                    MAIN_BEER => control::start_pour(),
                     MAIN_MUSIC => control::toggle_music(),
                     MAIN_IMU => control::toggle_imu(now_ms()),
                     MAIN_FLUIDS => enter_with_imu(&mut ui, Screen::Fluids),
@@ -171,8 +192,8 @@ pub fn on_input(ev: Event) {
                     control::dimmer();
                     control::volume_down();
                 }
-                // Click fires the automatic beer pour instantly; exit via 3s hold.
-                Event::Click => control::signal_beer_arrived(),
+                // Click starts the automatic beer pour; exit via 3s hold.
+                Event::Click => control::start_pour(),
                 Event::Hold => {
                     control::set_party(false);
                     control::set_music(false);
@@ -182,7 +203,7 @@ pub fn on_input(ev: Event) {
                 }
             },
             Screen::BeerManual => match ev {
-                // Rotation drives the servo and flows a (fast) beer byte; click exits.
+                // Rotation drives the servo and flows a (fast) beer packet; click exits.
                 Event::Left => {
                     control::servo_step(true);
                     control::start_beer();
@@ -198,19 +219,53 @@ pub fn on_input(ev: Event) {
                 }
                 Event::Hold => {}
             },
-            Screen::Controls if ui.editing => match ev {
-                Event::Left => edit_value(ui.cursor, false),
-                Event::Right => edit_value(ui.cursor, true),
-                Event::Click => ui.editing = false,
-                Event::Hold => {}
-            },
+            Screen::Controls if ui.editing => {
+                ui.last_input_ms = now_ms();
+                match ev {
+                    Event::Left => edit_value(ui.cursor, false),
+                    Event::Right => edit_value(ui.cursor, true),
+                    // Clicks on the beat tap the tempo in; it stops editing
+                    // by itself once they stop.
+                    Event::Click if ui.cursor == CONTROLS_TEMPO => tap_tempo(&mut ui),
+                    Event::Click => ui.editing = false,
+                    Event::Hold => {}
+                }
+            }
             Screen::Controls => match ev {
                 Event::Left => ui.cursor = wrap_prev(ui.cursor, CONTROL_ITEMS.len()),
                 Event::Right => ui.cursor = wrap_next(ui.cursor, CONTROL_ITEMS.len()),
-                Event::Click => {
-                    if ui.cursor == CONTROLS_BACK {
+                Event::Click => match ui.cursor {
+                    CONTROLS_BACK => {
                         ui.screen = Screen::Main;
                         ui.cursor = MAIN_CONTROLS;
+                    }
+                    CONTROLS_EFFECTS => {
+                        ui.screen = Screen::Effects;
+                        ui.cursor = 0;
+                    }
+                    // The click that starts editing is the first tap.
+                    CONTROLS_TEMPO => {
+                        ui.editing = true;
+                        ui.last_input_ms = now_ms();
+                        tap_tempo(&mut ui);
+                    }
+                    _ => ui.editing = true,
+                },
+                Event::Hold => {}
+            },
+            Screen::Effects if ui.editing => match ev {
+                Event::Left => edit_setting(ui.cursor, false),
+                Event::Right => edit_setting(ui.cursor, true),
+                Event::Click => ui.editing = false,
+                Event::Hold => {}
+            },
+            Screen::Effects => match ev {
+                Event::Left => ui.cursor = wrap_prev(ui.cursor, EFFECT_ITEMS),
+                Event::Right => ui.cursor = wrap_next(ui.cursor, EFFECT_ITEMS),
+                Event::Click => {
+                    if ui.cursor == EFFECTS_BACK {
+                        ui.screen = Screen::Controls;
+                        ui.cursor = CONTROLS_EFFECTS;
                     } else {
                         ui.editing = true;
                     }
@@ -244,7 +299,8 @@ fn enter_with_imu(ui: &mut Ui, target: Screen) {
     }
 }
 
-/// Called each display frame: auto-enter a pending IMU screen once ready.
+/// Called each display frame: auto-enter a pending IMU screen once ready, and
+/// end a tempo edit once the taps have stopped.
 pub fn poll() {
     critical_section::with(|cs| {
         let mut ui = UI.borrow_ref_mut(cs);
@@ -255,9 +311,20 @@ pub fn poll() {
                 ui.pending = None;
             }
         }
+        let tapping = ui.screen == Screen::Controls && ui.editing && ui.cursor == CONTROLS_TEMPO;
+        if tapping && now_ms().wrapping_sub(ui.last_input_ms) > TEMPO_EDIT_MS {
+            ui.editing = false;
+        }
     });
 }
 
+/// One tap of the tempo; two or more in a row set it.
+fn tap_tempo(ui: &mut Ui) {
+    if let Some(tempo) = ui.taps.tap(now_ms()) {
+        control::set_tempo(Some(tempo));
+    }
+}
+
 fn edit_value(item: usize, up: bool) {
     match item {
         0 => {
@@ -281,10 +348,22 @@ fn edit_value(item: usize, up: bool) {
                 control::dimmer()
             }
         }
+        3 => control::cycle_framing(up),
+        4 => control::cycle_theme(up),
+        5 => control::toggle_ambient(),
+        CONTROLS_TEMPO => control::nudge_tempo(up, now_ms()),
+        CONTROLS_POWER => control::step_led_budget(up),
         _ => {}
     }
 }
 
+/// Step Effect setting `index` within its parameter's range.
+fn edit_setting(index: usize, up: bool) {
+    if let Some((_, param)) = led_strip::setting(index) {
+        control::set_effect_param(index, param.stepped(control::effect_param(index), up));
+    }
+}
+
 fn wrap_next(i: usize, len: usize) -> usize {
     (i + 1) % len
 }
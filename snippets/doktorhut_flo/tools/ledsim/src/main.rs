@@ -0,0 +1,452 @@
+//! ledsim: run a `led_fx` effect on the host and look at it without a board.
+//! Run it from this directory (which pins the host target and a stable
+//! toolchain), e.g. `cargo run --release -- party --png party.png`.
+//!
+//! ```text
+//! ledsim party-ball --frames 500 --script tilt.txt --png party.png
+//! ledsim stream --speed 12 --play
+//! ledsim spectrum --wav song.wav --frames 2000 --gif spectrum.gif --every 4
+//! ledsim plasma2d --matrix 16x16 --gif plasma.gif
+//! ```
+
+mod music;
+mod output;
+mod script;
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::{env, fs, thread, time::Duration};
+
+use led_fx::audio::Audio;
+use led_fx::color::{brightness_q16, encode_channel, rgb, scale};
+use led_fx::effects::framing::{I2c, Spi, Uart};
+use led_fx::effects::source::{Random, Text};
+use led_fx::effects::spectrum::MODE_SPECTRUM;
+use led_fx::effects::{
+    BeerByte, Breathe, ByteSource, Chase, Comet, Ctx, Effect, Fire, Framed, Larson, Packet, Party,
+    Plasma, Plasma2d, Spectrum, Stream, Twinkle,
+};
+use led_fx::matrix::{Layout, XyMap};
+use led_fx::palette::{Theme, HEAT};
+use led_fx::tempo::{Beat, Tempo};
+use led_fx::{Rgb, MAX_LEDS};
+
+use music::Music;
+use output::Frame;
+use script::{Inputs, Script};
+
+const USAGE: &str = "\
+usage: ledsim <effect> [options]
+
+effects: stream, uart, i2c, spi, packet, beer, breathe, party, party-ball,
+         comet, twinkle, fire, larson, chase, plasma, vu, spectrum, plasma2d
+
+options:
+  --len N            strip length (default 60)
+  --matrix WxH       a W x H panel instead of a strip (its LEDs run along the
+                     wiring for 1D effects); GIF/ANSI show it as mounted
+  --layout L         panel wiring: serpentine (default) or rows
+  --frames N         frames to run (default 250)
+  --frame-ms N       frame time (default 8, as on the board)
+  --byte B[,B..]     byte for packet (default 0b10110010), bytes for beer
+                     (default the servo's first pour step packet)
+  --text MSG         stream/uart/i2c/spi: the bytes of MSG instead of random
+  --theme T          colour theme: rainbow, lava, ocean, beer, heat
+                     (default rainbow; fire keeps heat unless given)
+  --param NAME=V     set one of the effect's tunable parameters, e.g.
+                     --param 'bit width=3' (repeatable)
+  --trail MS         motion-blur trail for the travelling bits (time constant)
+  --speed S          speed setting 1..20 (default 6)
+  --brightness L     brightness level 1..8 (default 5)
+  --roll DEG         IMU roll (default 0)
+  --accel X,Y,Z      IMU acceleration in g (default 0,0,1)
+  --wav FILE         music for vu/spectrum (16-bit PCM WAV; default silence)
+  --bpm N            run the tempo clock from the first frame and pulse on the
+                     beat, as the firmware does for music, party and ambient
+  --script FILE      input changes over time (see script.rs)
+  --png FILE         timeline, one row per frame
+  --gif FILE         animation
+  --scale N          pixels per LED in PNG/GIF (default 4)
+  --every N          keep every Nth frame for GIF/ANSI (default 1)
+  --ansi             print a truecolor timeline
+  --play             animate in the terminal in real time";
+
+const PACKET_BYTE: u8 = 0b1011_0010;
+// Firmware show defaults (led_strip). BEER carries servo::goal_position_packet
+// of the first pour step (servo 1 to 2000).
+const BEER_PACKET: [u8; 9] = [0xFF, 0xFF, 0x01, 0x05, 0x03, 0x2A, 0xD0, 0x07, 0xF5];
+// The MUSIC strip's breathing pulse from before it went audio-reactive.
+const MUSIC_COLOR: Rgb = rgb(0, 255, 90);
+const MUSIC_PERIOD_MS: u32 = 2000;
+const MUSIC_FLOOR_Q16: u32 = 10280;
+// Same beat pulse as led_strip's (BEAT_FLOOR_Q16, OFFBEAT_PEAK_Q16).
+const BEAT_FLOOR_Q16: u32 = 26214;
+const OFFBEAT_PEAK_Q16: u32 = 49152;
+
+struct Options {
+    effect: String,
+    len: usize,
+    matrix: Option<XyMap>,
+    frames: usize,
+    frame_ms: u32,
+    bytes: Option<Vec<u8>>,
+    text: Option<String>,
+    theme: Option<Theme>,
+    params: Vec<(String, i32)>,
+    trail_ms: Option<u32>,
+    inputs: Inputs,
+    script: Script,
+    music: Option<Music>,
+    tempo: Option<Tempo>,
+    png: Option<PathBuf>,
+    gif: Option<PathBuf>,
+    scale: usize,
+    every: usize,
+    ansi: bool,
+    play: bool,
+}
+
+fn parse_byte(s: &str) -> Option<u8> {
+    let s = s.replace('_', "");
+    if let Some(bits) = s.strip_prefix("0b") {
+        u8::from_str_radix(bits, 2).ok()
+    } else if let Some(hex) = s.strip_prefix("0x") {
+        u8::from_str_radix(hex, 16).ok()
+    } else {
+        s.parse().ok()
+    }
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut args = env::args().skip(1);
+    let effect = args.next().ok_or("missing effect")?;
+    let mut o = Options {
+        effect,
+        len: 60,
+        matrix: None,
+        frames: 250,
+        frame_ms: 8,
+        bytes: None,
+        text: None,
+        theme: None,
+        params: Vec::new(),
+        trail_ms: None,
+        inputs: Inputs::default(),
+        script: Script::default(),
+        music: None,
+        tempo: None,
+        png: None,
+        gif: None,
+        scale: 4,
+        every: 1,
+        ansi: false,
+        play: false,
+    };
+    while let Some(flag) = args.next() {
+        let mut value = || args.next().ok_or(format!("{flag}: missing value"));
+        let num = |v: String| {
+            v.parse::<usize>()
+                .map_err(|_| format!("{flag}: bad number {v:?}"))
+        };
+        match flag.as_str() {
+            "--len" => o.len = num(value()?)?,
+            "--matrix" => {
+                let v = value()?;
+                let (w, h) = v
+                    .split_once('x')
+                    .ok_or(format!("--matrix: want WxH, got {v:?}"))?;
+                let (w, h) = (num(w.into())?, num(h.into())?);
+                let layout = o.matrix.map_or(Layout::Serpentine, |m| m.layout);
+                o.matrix = Some(XyMap::new(w, h, layout));
+            }
+            "--layout" => {
+                let layout = match value()?.as_str() {
+                    "serpentine" => Layout::Serpentine,
+                    "rows" => Layout::RowMajor,
+                    v => return Err(format!("--layout: unknown layout {v:?}")),
+                };
+                let m = o.matrix.unwrap_or(XyMap::new(0, 0, layout));
+                o.matrix = Some(XyMap { layout, ..m });
+            }
+            "--frames" => o.frames = num(value()?)?,
+            "--frame-ms" => o.frame_ms = num(value()?)? as u32,
+            "--byte" => {
+                let v = value()?;
+                let bytes = v.split(',').map(parse_byte).collect::<Option<Vec<_>>>();
+                o.bytes = Some(bytes.ok_or(format!("--byte: bad byte in {v:?}"))?);
+            }
+            "--text" => o.text = Some(value()?),
+            "--theme" => {
+                let v = value()?;
+                let theme = Theme::ALL
+                    .into_iter()
+                    .find(|t| t.name().eq_ignore_ascii_case(&v));
+                o.theme = Some(theme.ok_or(format!("--theme: unknown theme {v:?}"))?);
+            }
+            "--param" => {
+                let v = value()?;
+                let (name, n) = v
+                    .split_once('=')
+                    .ok_or(format!("--param: want NAME=V, got {v:?}"))?;
+                let n = n
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("--param: bad value in {v:?}"))?;
+                o.params.push((name.trim().to_string(), n));
+            }
+            "--trail" => o.trail_ms = Some(num(value()?)? as u32),
+            "--speed" | "--brightness" | "--roll" => {
+                let v = value()?;
+                o.inputs.set(&flag[2..], &[&v])?;
+            }
+            "--accel" => {
+                let v = value()?;
+                o.inputs.set("accel", &v.split(',').collect::<Vec<_>>())?;
+            }
+            "--script" => {
+                let path = value()?;
+                let text = fs::read_to_string(&path).map_err(|e| format!("{path}: {e}"))?;
+                o.script = Script::parse(&text).map_err(|e| format!("{path}: {e}"))?;
+            }
+            "--wav" => {
+                let path = value()?;
+                let bytes = fs::read(&path).map_err(|e| format!("{path}: {e}"))?;
+                o.music = Some(Music::parse(&bytes).map_err(|e| format!("{path}: {e}"))?);
+            }
+            "--bpm" => o.tempo = Some(Tempo::from_bpm(num(value()?)? as u32, 0)),
+            "--png" => o.png = Some(value()?.into()),
+            "--gif" => o.gif = Some(value()?.into()),
+            "--scale" => o.scale = num(value()?)?.max(1),
+            "--every" => o.every = num(value()?)?.max(1),
+            "--ansi" => o.ansi = true,
+            "--play" => o.play = true,
+            _ => return Err(format!("unknown option {flag:?}")),
+        }
+    }
+    if let Some(m) = o.matrix {
+        o.len = m.len();
+    }
+    if !(1..=MAX_LEDS).contains(&o.len) {
+        return Err(format!(
+            "--len (or the --matrix size) must be 1..={MAX_LEDS}"
+        ));
+    }
+    Ok(o)
+}
+
+/// Bytes for the stream effects: random, or `--text`.
+enum Bytes {
+    Random(Random),
+    Text(Text),
+}
+
+impl ByteSource for Bytes {
+    fn next_byte(&mut self) -> Option<u8> {
+        match self {
+            Bytes::Random(r) => r.next_byte(),
+            Bytes::Text(t) => t.next_byte(),
+        }
+    }
+
+    fn gap(&mut self) -> u32 {
+        match self {
+            Bytes::Random(r) => r.gap(),
+            Bytes::Text(t) => t.gap(),
+        }
+    }
+}
+
+/// `effect` with the `--trail` applied by `with_trail`, if given.
+fn trailed<E: Effect + 'static>(
+    effect: E,
+    trail: Option<u32>,
+    with_trail: fn(E, u32) -> E,
+) -> Box<dyn Effect> {
+    match trail {
+        Some(ms) => Box::new(with_trail(effect, ms)),
+        None => Box::new(effect),
+    }
+}
+
+/// The panel, or a strip as a one-row matrix.
+fn map(o: &Options) -> XyMap {
+    o.matrix.unwrap_or(XyMap::new(o.len, 1, Layout::RowMajor))
+}
+
+fn make_effect(o: &Options) -> Result<Box<dyn Effect>, String> {
+    let trail = o.trail_ms;
+    let bytes = || match &o.text {
+        // Sources borrow their text for good; leak the one copy.
+        Some(t) => Bytes::Text(Text::new(Box::leak(t.as_bytes().into()))),
+        None => Bytes::Random(Random::default()),
+    };
+    let byte = o.bytes.as_ref().map_or(PACKET_BYTE, |b| b[0]);
+    let mut beer = BeerByte::new(0);
+    beer.set_bytes(o.bytes.as_deref().unwrap_or(&BEER_PACKET));
+    Ok(match o.effect.as_str() {
+        "stream" => trailed(
+            Stream::with_source(o.len, bytes()),
+            trail,
+            Stream::with_trail,
+        ),
+        "uart" => trailed(
+            Framed::with_source(o.len, Uart, bytes()),
+            trail,
+            Framed::with_trail,
+        ),
+        "i2c" => trailed(
+            Framed::with_source(o.len, I2c::new(), bytes()),
+            trail,
+            Framed::with_trail,
+        ),
+        "spi" => trailed(
+            Framed::with_source(o.len, Spi, bytes()),
+            trail,
+            Framed::with_trail,
+        ),
+        "packet" => trailed(Packet::new(byte), trail, Packet::with_trail),
+        "beer" => trailed(beer, trail, BeerByte::with_trail),
+        "breathe" => Box::new(Breathe::new(MUSIC_COLOR, MUSIC_PERIOD_MS, MUSIC_FLOOR_Q16)),
+        "party" => Box::new(Party::new(0, false)),
+        "party-ball" => Box::new(Party::new(0, true)),
+        "comet" => Box::new(Comet::new()),
+        "twinkle" => Box::new(Twinkle::new()),
+        "fire" => match o.theme {
+            Some(_) => Box::new(Fire::new()),
+            None => Box::new(Fire::new().with_palette(HEAT)),
+        },
+        "larson" => Box::new(Larson::new()),
+        "chase" => Box::new(Chase::new()),
+        "plasma" => Box::new(Plasma::new()),
+        "plasma2d" => Box::new(Plasma2d::new(map(o))),
+        "vu" => Box::new(Spectrum::new()),
+        "spectrum" => Box::new(Spectrum::new().with_mode(MODE_SPECTRUM)),
+        e => return Err(format!("unknown effect {e:?}")),
+    })
+}
+
+/// Opacity of a beat-synced show at `beat` (led_strip's `beat_pulse_q16`).
+fn beat_pulse_q16(beat: &Beat) -> u32 {
+    let peak = if beat.is_downbeat() {
+        65535
+    } else {
+        OFFBEAT_PEAK_Q16
+    };
+    BEAT_FLOOR_Q16 + (peak - BEAT_FLOOR_Q16) * beat.pulse_q16() / 65535
+}
+
+/// What the LEDs would emit for `fb` at brightness `level` (the driver's
+/// gamma + brightness), ready for display.
+fn emitted(fb: &[Rgb], level: u32) -> Frame {
+    let bright = brightness_q16(level);
+    fb.iter()
+        .map(|c| c.map(|v| output::to_srgb(encode_channel(v, bright))))
+        .collect()
+}
+
+/// `frame` (in wiring order) row by row as `map`'s panel is mounted, gaps
+/// black.
+fn as_mounted(frame: &Frame, map: &XyMap) -> Frame {
+    let mut out = Vec::with_capacity(map.width * map.height);
+    for y in 0..map.height {
+        for x in 0..map.width {
+            out.push(map.index(x, y).map_or([0; 3], |i| frame[i]));
+        }
+    }
+    out
+}
+
+fn run(mut o: Options) -> Result<(), String> {
+    let mut effect = make_effect(&o)?;
+    for (name, value) in &o.params {
+        let params = effect.params();
+        let Some(i) = params
+            .iter()
+            .position(|p| p.name.eq_ignore_ascii_case(name))
+        else {
+            let names: Vec<_> = params.iter().map(|p| p.name).collect();
+            return Err(format!(
+                "{}: no parameter {name:?} (has {names:?})",
+                o.effect
+            ));
+        };
+        effect.set_param(i, *value);
+    }
+    let mut fb = vec![[0u16; 3]; o.len];
+    let mut frames = Vec::with_capacity(o.frames);
+    let mut inputs = o.inputs;
+    for i in 0..o.frames {
+        let now_ms = i as u32 * o.frame_ms;
+        o.script.apply(now_ms, &mut inputs);
+        let ctx = Ctx {
+            now_ms,
+            dt_ms: if i == 0 { 0 } else { o.frame_ms },
+            len: o.len,
+            velocity_q8: inputs.velocity_q8(),
+            roll: inputs.roll,
+            accel: inputs.accel,
+            palette: o.theme.unwrap_or(Theme::Rainbow).palette(),
+            audio: o.music.as_mut().map_or(Audio::SILENT, |m| m.at(now_ms)),
+            beat: o.tempo.map(|t| t.at(now_ms)),
+        };
+        if i == 0 {
+            effect.on_enter(&ctx);
+        }
+        effect.tick(&ctx);
+        fb.fill([0, 0, 0]);
+        effect.render(&mut fb);
+        if let Some(beat) = &ctx.beat {
+            let opacity = beat_pulse_q16(beat);
+            fb.iter_mut().for_each(|px| *px = scale(*px, opacity));
+        }
+        frames.push(emitted(&fb, inputs.brightness));
+        if effect.is_finished() {
+            eprintln!("{} finished at frame {i} ({now_ms} ms)", o.effect);
+            break;
+        }
+    }
+
+    let io = |what: &str, r: std::io::Result<()>| r.map_err(|e| format!("{what}: {e}"));
+    // The PNG timeline follows the wiring; the rest show a panel as mounted.
+    if let Some(path) = &o.png {
+        io("png", output::write_png(path, &frames, o.scale))?;
+    }
+    let map = map(&o);
+    let mounted: Vec<Frame> = frames.iter().map(|f| as_mounted(f, &map)).collect();
+    if let Some(path) = &o.gif {
+        let delay_cs = (o.frame_ms as usize * o.every).div_ceil(10).max(2) as u16;
+        io(
+            "gif",
+            output::write_gif(path, &mounted, map.width, o.scale, o.every, delay_cs),
+        )?;
+    }
+    if o.ansi {
+        io("ansi", output::print_ansi(&mounted, map.width, o.every))?;
+    }
+    if o.play {
+        let period = Duration::from_millis(o.frame_ms as u64 * o.every as u64);
+        for (n, frame) in mounted.iter().step_by(o.every).enumerate() {
+            // Back up over the previous frame's rows (a strip has just one).
+            if n > 0 && map.height > 1 {
+                print!("\x1b[{}A", map.height - 1);
+            }
+            let rows: Vec<String> = frame.chunks(map.width).map(output::ansi_line).collect();
+            print!("\r{}", rows.join("\n"));
+            io("play", std::io::stdout().flush())?;
+            thread::sleep(period);
+        }
+        println!();
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match parse_args().and_then(run) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("ledsim: {e}\n\n{USAGE}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! `--wav`: a recording played alongside the effect, analysed as the firmware
+//! analyses its ADC input, so the audio-reactive effects can be tried on real
+//! music.
+
+use led_fx::audio::wav::Wav;
+use led_fx::audio::{Analyser, Audio, Resampler, SAMPLE_RATE};
+
+pub struct Music {
+    /// The recording at the analyser's rate.
+    samples: Vec<i16>,
+    fed: usize,
+    analyser: Analyser,
+    audio: Audio,
+    /// Time of the last beat (ms), if any.
+    beat_ms: Option<u32>,
+}
+
+impl Music {
+    pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+        let wav = Wav::parse(bytes).map_err(|e| format!("{e:?}"))?;
+        let mut resampler = Resampler::new(wav.sample_rate);
+        let mut samples = Vec::new();
+        for s in wav.samples() {
+            resampler.push(s, |s| samples.push(s));
+        }
+        Ok(Self {
+            samples,
+            fed: 0,
+            analyser: Analyser::new(),
+            audio: Audio::SILENT,
+            beat_ms: None,
+        })
+    }
+
+    /// The music at `now_ms`: the analysis of the last block played by then
+    /// (silence after the end).
+    pub fn at(&mut self, now_ms: u32) -> Audio {
+        let due = (now_ms as usize * SAMPLE_RATE as usize / 1000).min(self.samples.len());
+        while self.fed < due {
+            let analysis = self.analyser.push(self.samples[self.fed]);
+            self.fed += 1;
+            if let Some(a) = analysis {
+                if a.beat {
+                    self.beat_ms = Some((self.fed as u64 * 1000 / SAMPLE_RATE as u64) as u32);
+                }
+                self.audio.bands = a.bands;
+                self.audio.level = a.level;
+            }
+        }
+        if self.fed == self.samples.len() {
+            self.audio.bands = Audio::SILENT.bands;
+            self.audio.level = 0;
+        }
+        self.audio.since_beat_ms = self.beat_ms.map_or(u32::MAX, |b| now_ms - b);
+        self.audio
+    }
+}
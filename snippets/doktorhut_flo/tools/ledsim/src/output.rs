@@ -0,0 +1,110 @@
+//! Turning simulated frames into something to look at: a PNG timeline (one row
+//! per frame, time running down), an animated GIF, or truecolor ANSI cells.
+//! Frames of a matrix are row-major, `width` LEDs a row (a strip is one row).
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// What the LEDs emit, one `[r, g, b]` per LED, in sRGB for display.
+pub type Frame = Vec<[u8; 3]>;
+
+/// LED PWM duty (0..=255, linear light) -> sRGB, so a monitor shows roughly
+/// what the eye sees on the strip.
+pub fn to_srgb(duty: u8) -> u8 {
+    let linear = duty as f32 / 255.0;
+    let srgb = if linear <= 0.003_130_8 {
+        12.92 * linear
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    };
+    (srgb * 255.0).round() as u8
+}
+
+fn to_io(e: impl std::error::Error + Send + Sync + 'static) -> io::Error {
+    io::Error::other(e)
+}
+
+/// Each LED is `scale` px wide, each frame one row.
+pub fn write_png(path: &Path, frames: &[Frame], scale: usize) -> io::Result<()> {
+    let len = frames.first().map_or(0, Vec::len);
+    let width = len * scale;
+    let mut enc = png::Encoder::new(
+        BufWriter::new(File::create(path)?),
+        width as u32,
+        frames.len() as u32,
+    );
+    enc.set_color(png::ColorType::Rgb);
+    enc.set_depth(png::BitDepth::Eight);
+    let mut data = Vec::with_capacity(width * frames.len() * 3);
+    for frame in frames {
+        for px in frame {
+            for _ in 0..scale {
+                data.extend_from_slice(px);
+            }
+        }
+    }
+    enc.write_header()
+        .map_err(to_io)?
+        .write_image_data(&data)
+        .map_err(to_io)
+}
+
+/// An animation of every `every`th frame; each LED a `scale` x `scale` square.
+/// `delay_cs` is the GIF frame delay (1/100 s).
+pub fn write_gif(
+    path: &Path,
+    frames: &[Frame],
+    width: usize,
+    scale: usize,
+    every: usize,
+    delay_cs: u16,
+) -> io::Result<()> {
+    let len = frames.first().map_or(0, Vec::len);
+    let rows = len.div_ceil(width.max(1));
+    let (w, h) = ((width * scale) as u16, (rows * scale) as u16);
+    let mut enc =
+        gif::Encoder::new(BufWriter::new(File::create(path)?), w, h, &[]).map_err(to_io)?;
+    enc.set_repeat(gif::Repeat::Infinite).map_err(to_io)?;
+    for frame in frames.iter().step_by(every.max(1)) {
+        let mut pixels = Vec::with_capacity(w as usize * h as usize * 3);
+        for leds in frame.chunks(width.max(1)) {
+            let mut row = Vec::with_capacity(w as usize * 3);
+            for px in leds {
+                for _ in 0..scale {
+                    row.extend_from_slice(px);
+                }
+            }
+            pixels.extend(row.repeat(scale));
+        }
+        let mut f = gif::Frame::from_rgb_speed(w, h, &pixels, 10);
+        f.delay = delay_cs;
+        enc.write_frame(&f).map_err(to_io)?;
+    }
+    Ok(())
+}
+
+/// One row of LEDs as a line of truecolor cells (two spaces per LED).
+pub fn ansi_line(leds: &[[u8; 3]]) -> String {
+    let mut s = String::with_capacity(leds.len() * 24);
+    for &[r, g, b] in leds {
+        s.push_str(&format!("\x1b[48;2;{r};{g};{b}m  "));
+    }
+    s.push_str("\x1b[0m");
+    s
+}
+
+/// Print the frames as a timeline: one line each for a strip, a block of
+/// lines (and a blank line) each for a matrix.
+pub fn print_ansi(frames: &[Frame], width: usize, every: usize) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    for frame in frames.iter().step_by(every.max(1)) {
+        for leds in frame.chunks(width.max(1)) {
+            writeln!(out, "{}", ansi_line(leds))?;
+        }
+        if frame.len() > width {
+            writeln!(out)?;
+        }
+    }
+    Ok(())
+}
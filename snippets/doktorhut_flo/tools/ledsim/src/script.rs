@@ -0,0 +1,125 @@
+//! Scripted `control` inputs: the values the firmware would read from the
+//! encoder and IMU, changed at given times.
+//!
+//! One change per line, `<time_ms> <input> <value...>`; `#` starts a comment:
+//!
+//! ```text
+//! 0     speed 6
+//! 1000  roll -30
+//! 1500  accel 0.2 0.0 2.5
+//! 2000  brightness 8
+//! ```
+
+use std::fmt;
+
+#[derive(Clone, Copy)]
+pub struct Inputs {
+    /// Encoder speed setting (1..=20).
+    pub speed: u32,
+    /// Brightness level (1..=8).
+    pub brightness: u32,
+    /// IMU roll (deg) and acceleration (g).
+    pub roll: i32,
+    pub accel: [f32; 3],
+}
+
+impl Default for Inputs {
+    // The firmware's boot values (at rest: 1 g down).
+    fn default() -> Self {
+        Self {
+            speed: 6,
+            brightness: 5,
+            roll: 0,
+            accel: [0.0, 0.0, 1.0],
+        }
+    }
+}
+
+// Same mapping as `control::velocity_q8`.
+const SPEED_MIN: u32 = 1;
+const SPEED_MAX: u32 = 20;
+const VEL_MIN_Q8: u32 = 8;
+const VEL_MAX_Q8: u32 = 1024;
+
+impl Inputs {
+    /// LED velocity (Q8.8 LEDs per reference frame) for `speed`.
+    pub fn velocity_q8(&self) -> u32 {
+        let s = self.speed.clamp(SPEED_MIN, SPEED_MAX) - SPEED_MIN;
+        VEL_MIN_Q8 + (VEL_MAX_Q8 - VEL_MIN_Q8) * s / (SPEED_MAX - SPEED_MIN)
+    }
+
+    /// Set `input` from its textual value(s).
+    pub fn set(&mut self, input: &str, values: &[&str]) -> Result<(), String> {
+        let num = |i: usize| -> Result<f32, String> {
+            let v = values.get(i).ok_or(format!("{input}: missing value"))?;
+            v.parse().map_err(|_| format!("{input}: bad value {v:?}"))
+        };
+        match input {
+            "speed" => self.speed = num(0)? as u32,
+            "brightness" => self.brightness = (num(0)? as u32).clamp(1, 8),
+            "roll" => self.roll = num(0)? as i32,
+            "accel" => self.accel = [num(0)?, num(1)?, num(2)?],
+            _ => return Err(format!("unknown input {input:?}")),
+        }
+        Ok(())
+    }
+}
+
+struct Change {
+    at_ms: u32,
+    input: String,
+    values: Vec<String>,
+}
+
+/// Input changes, in time order.
+#[derive(Default)]
+pub struct Script {
+    changes: Vec<Change>,
+    next: usize,
+}
+
+#[derive(Debug)]
+pub struct ParseError {
+    line: usize,
+    msg: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.msg)
+    }
+}
+
+impl Script {
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut changes = Vec::new();
+        for (n, line) in text.lines().enumerate() {
+            let err = |msg: String| ParseError { line: n + 1, msg };
+            let line = line.split('#').next().unwrap_or("");
+            let mut words = line.split_whitespace();
+            let Some(at) = words.next() else { continue };
+            let at_ms = at.parse().map_err(|_| err(format!("bad time {at:?}")))?;
+            let input = words.next().ok_or(err("missing input".into()))?.to_string();
+            let values: Vec<String> = words.map(str::to_string).collect();
+            // Validate now rather than halfway through a run.
+            let refs: Vec<&str> = values.iter().map(String::as_str).collect();
+            Inputs::default().set(&input, &refs).map_err(err)?;
+            changes.push(Change {
+                at_ms,
+                input,
+                values,
+            });
+        }
+        changes.sort_by_key(|c| c.at_ms); // stable: same-time lines keep order
+        Ok(Self { changes, next: 0 })
+    }
+
+    /// Apply every change due by `now_ms`.
+    pub fn apply(&mut self, now_ms: u32, inputs: &mut Inputs) {
+        while let Some(c) = self.changes.get(self.next).filter(|c| c.at_ms <= now_ms) {
+            let refs: Vec<&str> = c.values.iter().map(String::as_str).collect();
+            inputs.set(&c.input, &refs).expect("validated in parse");
+            self.next += 1;
+        }
+    }
+}